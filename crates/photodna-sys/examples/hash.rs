@@ -126,12 +126,12 @@ fn main() {
             );
         } else {
             println!("Border detection returned {} hash(es)", border_result);
-            for i in 0..border_result as usize {
-                let result = hash_results[i].result;
-                let x = hash_results[i].header_dimensions_image_x;
-                let y = hash_results[i].header_dimensions_image_y;
-                let w = hash_results[i].header_dimensions_image_w;
-                let h = hash_results[i].header_dimensions_image_h;
+            for (i, hash_result) in hash_results.iter().enumerate().take(border_result as usize) {
+                let result = hash_result.result;
+                let x = hash_result.header_dimensions_image_x;
+                let y = hash_result.header_dimensions_image_y;
+                let w = hash_result.header_dimensions_image_w;
+                let h = hash_result.header_dimensions_image_h;
                 println!(
                     "  Hash {}: result={}, region=({},{},{},{})",
                     i, result, x, y, w, h
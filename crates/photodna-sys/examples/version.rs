@@ -41,6 +41,7 @@ fn main() {
         match EdgeHashGenerator::new(None, 4) {
             Ok(lib) => {
                 println!("✓ Library loaded successfully!");
+                println!("  Path: {}", lib.library_path().display());
                 println!();
                 println!("Library Version Information:");
                 println!("  Version (packed): 0x{:08x}", lib.library_version());
@@ -51,6 +51,7 @@
 //! |-----------|-------------|
 //! | [`EdgeHashGenerator`] | Main wrapper that loads the library and exposes function access |
 //! | [`HashResult`] | C-compatible struct for border detection results |
+//! | [`Options`] | Builder for `PhotoDnaOptions` that rejects conflicting flags |
 //! | `PhotoDnaOptions` | Bitmask flags for pixel format, hash format, and behavior |
 //! | `Fn*` types | Function pointer types matching the C API signatures |
 //!
@@ -215,6 +216,9 @@ use std::ffi::{c_char, c_void, CStr};
 #[cfg(not(photodna_no_sdk))]
 use std::ffi::CString;
 
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+use std::path::{Path, PathBuf};
+
 // ============================================================================
 // Constants
 // ============================================================================
@@ -396,6 +400,183 @@ pub const PhotoDna_Test: PhotoDnaOptions = 0x60000000;
 /// Use same options as specified for primary parameter.
 pub const PhotoDna_Other: PhotoDnaOptions = 0xffffffff; // -1 as u32
 
+/// Pixel layout values defined by the SDK header.
+const PIXEL_LAYOUTS: [PhotoDnaOptions; 9] = [
+    PhotoDna_Rgb,
+    PhotoDna_Rgba,
+    PhotoDna_Argb,
+    PhotoDna_Cmyk,
+    PhotoDna_Grey8,
+    PhotoDna_Grey32,
+    PhotoDna_YCbCr,
+    PhotoDna_RgbaPm,
+    PhotoDna_Yuv420p,
+];
+
+/// Builder for [`PhotoDnaOptions`] that rejects conflicting flags.
+///
+/// The pixel layout and hash format each occupy a multi-bit field, so
+/// OR-ing two layouts together silently produces a third (for example
+/// `PhotoDna_Rgba | PhotoDna_Argb` is `PhotoDna_Cmyk`). This builder sets
+/// each field at most once and reports a second, different value from
+/// [`build`](Self::build) instead. Layouts that share a value, such as
+/// [`PhotoDna_Rgb`] and [`PhotoDna_Bgr`], do not conflict.
+///
+/// The raw constants remain available for code that builds flags by hand.
+///
+/// # Example
+///
+/// ```rust
+/// use photodna_sys::*;
+///
+/// let options = Options::new()
+///     .pixel_format(PhotoDna_Rgba)
+///     .hash_format(PhotoDna_HashFormatEdgeV2)
+///     .remove_border(true)
+///     .build()?;
+/// assert_eq!(options, PhotoDna_Rgba | PhotoDna_HashFormatEdgeV2 | PhotoDna_RemoveBorder);
+///
+/// let conflicting = Options::new()
+///     .pixel_format(PhotoDna_Rgba)
+///     .pixel_format(PhotoDna_Argb)
+///     .build();
+/// assert_eq!(
+///     conflicting,
+///     Err(OptionsError::ConflictingPixelFormats(PhotoDna_Rgba, PhotoDna_Argb))
+/// );
+/// # Ok::<(), OptionsError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Options {
+    /// Pixel layout, if set.
+    pixel_format: Option<PhotoDnaOptions>,
+    /// Hash format, if set.
+    hash_format: Option<PhotoDnaOptions>,
+    /// Single-bit flags.
+    flags: PhotoDnaOptions,
+    /// The first conflict found, reported by `build`.
+    error: Option<OptionsError>,
+}
+
+impl Options {
+    /// Creates an empty set of options, equivalent to [`PhotoDna_Default`].
+    pub const fn new() -> Self {
+        Self {
+            pixel_format: None,
+            hash_format: None,
+            flags: 0,
+            error: None,
+        }
+    }
+
+    /// Sets the pixel layout, one of the `PhotoDna_Rgb`..`PhotoDna_Yuv420p`
+    /// constants.
+    pub fn pixel_format(mut self, layout: PhotoDnaOptions) -> Self {
+        if !PIXEL_LAYOUTS.contains(&layout) {
+            self.fail(OptionsError::InvalidPixelFormat(layout));
+        } else if let Some(previous) = self.pixel_format.filter(|&p| p != layout) {
+            self.fail(OptionsError::ConflictingPixelFormats(previous, layout));
+        } else {
+            self.pixel_format = Some(layout);
+        }
+        self
+    }
+
+    /// Sets the hash format, [`PhotoDna_HashFormatEdgeV2`] or
+    /// [`PhotoDna_HashFormatEdgeV2Base64`].
+    pub fn hash_format(mut self, format: PhotoDnaOptions) -> Self {
+        if format != PhotoDna_HashFormatEdgeV2 && format != PhotoDna_HashFormatEdgeV2Base64 {
+            self.fail(OptionsError::InvalidHashFormat(format));
+        } else if let Some(previous) = self.hash_format.filter(|&f| f != format) {
+            self.fail(OptionsError::ConflictingHashFormats(previous, format));
+        } else {
+            self.hash_format = Some(format);
+        }
+        self
+    }
+
+    /// Sets or clears [`PhotoDna_RemoveBorder`].
+    pub fn remove_border(self, enable: bool) -> Self {
+        self.flag(PhotoDna_RemoveBorder, enable)
+    }
+
+    /// Sets or clears [`PhotoDna_NoRotateFlip`].
+    pub fn no_rotate_flip(self, enable: bool) -> Self {
+        self.flag(PhotoDna_NoRotateFlip, enable)
+    }
+
+    /// Sets or clears [`PhotoDna_CheckMemory`].
+    pub fn check_memory(self, enable: bool) -> Self {
+        self.flag(PhotoDna_CheckMemory, enable)
+    }
+
+    /// Sets or clears [`PhotoDna_Verbose`].
+    pub fn verbose(self, enable: bool) -> Self {
+        self.flag(PhotoDna_Verbose, enable)
+    }
+
+    /// Returns the combined flags.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first invalid or conflicting value passed to
+    /// [`pixel_format`](Self::pixel_format) or
+    /// [`hash_format`](Self::hash_format).
+    pub fn build(self) -> Result<PhotoDnaOptions, OptionsError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.pixel_format.unwrap_or(0) | self.hash_format.unwrap_or(0) | self.flags),
+        }
+    }
+
+    fn flag(mut self, bit: PhotoDnaOptions, enable: bool) -> Self {
+        if enable {
+            self.flags |= bit;
+        } else {
+            self.flags &= !bit;
+        }
+        self
+    }
+
+    fn fail(&mut self, error: OptionsError) {
+        self.error.get_or_insert(error);
+    }
+}
+
+/// Why [`Options::build`] failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionsError {
+    /// Two different pixel layouts were set: the first, then the second.
+    ConflictingPixelFormats(PhotoDnaOptions, PhotoDnaOptions),
+    /// Two different hash formats were set: the first, then the second.
+    ConflictingHashFormats(PhotoDnaOptions, PhotoDnaOptions),
+    /// The value is not a pixel layout defined by the SDK.
+    InvalidPixelFormat(PhotoDnaOptions),
+    /// The value is not a hash format defined by the SDK.
+    InvalidHashFormat(PhotoDnaOptions),
+}
+
+impl std::fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ConflictingPixelFormats(first, second) => write!(
+                f,
+                "conflicting pixel formats {:#06x} and {:#06x}",
+                first, second
+            ),
+            Self::ConflictingHashFormats(first, second) => write!(
+                f,
+                "conflicting hash formats {:#06x} and {:#06x}",
+                first, second
+            ),
+            Self::InvalidPixelFormat(value) => write!(f, "invalid pixel format {:#06x}", value),
+            Self::InvalidHashFormat(value) => write!(f, "invalid hash format {:#06x}", value),
+        }
+    }
+}
+
+impl std::error::Error for OptionsError {}
+
 // ============================================================================
 // Structures
 // ============================================================================
@@ -645,6 +826,147 @@ pub use native::*;
 // Edge Hash Generator
 // ============================================================================
 
+/// Why [`EdgeHashGenerator::load`] failed.
+///
+/// Lists every candidate library path that was tried, in order, with the
+/// loader's error for each. Failures after a library was loaded (a missing
+/// symbol, or initialization failing) are described by
+/// [`reason`](Self::reason).
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+#[derive(Debug)]
+pub struct LibraryLoadReport {
+    /// Summary of the failure.
+    reason: String,
+    /// Each path that failed to load, with the loader's error.
+    attempts: Vec<(PathBuf, libloading::Error)>,
+}
+
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+impl LibraryLoadReport {
+    /// Returns a summary of the failure.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    /// Returns each library path that failed to load, in the order tried.
+    pub fn attempts(&self) -> &[(PathBuf, libloading::Error)] {
+        &self.attempts
+    }
+}
+
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+impl From<String> for LibraryLoadReport {
+    fn from(reason: String) -> Self {
+        Self {
+            reason,
+            attempts: Vec::new(),
+        }
+    }
+}
+
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+impl std::fmt::Display for LibraryLoadReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.reason)?;
+        for (path, error) in &self.attempts {
+            write!(f, "\n  tried '{}': {}", path.display(), error)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+impl std::error::Error for LibraryLoadReport {}
+
+/// Every symbol [`EdgeHashGenerator::new`] resolves, in the order it
+/// resolves them.
+pub const EXPECTED_SYMBOLS: [&str; 13] = [
+    "EdgeHashGeneratorInit",
+    "EdgeHashGeneratorRelease",
+    "GetErrorNumber",
+    "GetErrorString",
+    "LibraryVersion",
+    "LibraryVersionMajor",
+    "LibraryVersionMinor",
+    "LibraryVersionPatch",
+    "LibraryVersionText",
+    "PhotoDnaEdgeHash",
+    "PhotoDnaEdgeHashBorder",
+    "PhotoDnaEdgeHashBorderSub",
+    "PhotoDnaEdgeHashSub",
+];
+
+/// Which of the [`EXPECTED_SYMBOLS`] a library exports, as found by
+/// [`EdgeHashGenerator::probe`].
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolReport {
+    /// Path of the probed library.
+    library_path: PathBuf,
+    /// Each expected symbol and whether it was found, in
+    /// [`EXPECTED_SYMBOLS`] order.
+    symbols: Vec<(&'static str, bool)>,
+}
+
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+impl SymbolReport {
+    /// Builds a report by asking `resolve` about each expected symbol.
+    fn resolve_with(library_path: PathBuf, mut resolve: impl FnMut(&str) -> bool) -> Self {
+        let symbols = EXPECTED_SYMBOLS
+            .iter()
+            .map(|&name| (name, resolve(name)))
+            .collect();
+        Self {
+            library_path,
+            symbols,
+        }
+    }
+
+    /// Returns the path of the probed library.
+    pub fn library_path(&self) -> &Path {
+        &self.library_path
+    }
+
+    /// Returns each expected symbol and whether the library exports it.
+    pub fn symbols(&self) -> &[(&'static str, bool)] {
+        &self.symbols
+    }
+
+    /// Returns whether the library exports `name`, or `None` if `name` is
+    /// not an expected symbol.
+    pub fn is_found(&self, name: &str) -> Option<bool> {
+        self.symbols
+            .iter()
+            .find(|(symbol, _)| *symbol == name)
+            .map(|&(_, found)| found)
+    }
+
+    /// Returns the expected symbols the library does not export.
+    pub fn missing(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.symbols
+            .iter()
+            .filter(|(_, found)| !found)
+            .map(|&(name, _)| name)
+    }
+
+    /// Returns `true` if every expected symbol was found.
+    pub fn is_complete(&self) -> bool {
+        self.symbols.iter().all(|&(_, found)| found)
+    }
+}
+
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+impl std::fmt::Display for SymbolReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.library_path.display())?;
+        for (name, found) in &self.symbols {
+            let status = if *found { "found" } else { "missing" };
+            write!(f, "\n  {}: {}", name, status)?;
+        }
+        Ok(())
+    }
+}
+
 /// The PhotoDNA Edge Hash Generator library wrapper.
 ///
 /// This struct handles loading the native library and provides access to all
@@ -662,6 +984,8 @@ pub use native::*;
 pub struct EdgeHashGenerator {
     /// Handle to the loaded dynamic library.
     _library: libloading::Library,
+    /// Path the dynamic library was loaded from.
+    library_path: PathBuf,
     /// Handle to the PhotoDNA library instance.
     library_instance: *mut c_void,
     /// Function pointer: EdgeHashGeneratorRelease
@@ -696,13 +1020,18 @@ impl EdgeHashGenerator {
     ///
     /// # Parameters
     ///
-    /// - `library_dir`: Directory containing the library. If `None`, uses the path from `PHOTODNA_LIB_DIR`.
+    /// - `library_dir`: Directory containing the library. If `None`, uses the
+    ///   build-time [`PHOTODNA_LIB_DIR`]; the environment is not consulted at
+    ///   runtime.
     /// - `max_threads`: Maximum number of concurrent threads. Calls exceeding this
-    ///   will block until a previous call completes.
+    ///   will block until a previous call completes. Must be at least 1.
     ///
     /// # Returns
     ///
     /// A Result containing the EdgeHashGenerator or an error message.
+    /// Returns an error without calling into the library if `max_threads < 1`.
+    /// Use [`load`](Self::load) to get a structured [`LibraryLoadReport`]
+    /// instead of a message.
     ///
     /// # Example
     ///
@@ -714,28 +1043,76 @@ impl EdgeHashGenerator {
     /// let lib = EdgeHashGenerator::new(Some("/path/to/libs"), 4)?;
     /// ```
     pub fn new(library_dir: Option<&str>, max_threads: i32) -> Result<Self, String> {
+        Self::load(library_dir, max_threads).map_err(|report| report.to_string())
+    }
+
+    /// Creates a new EdgeHashGenerator, reporting every path that failed to load.
+    ///
+    /// Behaves like [`new`](Self::new), but on failure returns a
+    /// [`LibraryLoadReport`] listing each candidate library path that was
+    /// tried together with the loader's error for it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// match EdgeHashGenerator::load(Some("/opt/photodna"), 4) {
+    ///     Ok(lib) => println!("loaded {}", lib.library_path().display()),
+    ///     Err(report) => {
+    ///         for (path, error) in report.attempts() {
+    ///             eprintln!("{}: {}", path.display(), error);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn load(library_dir: Option<&str>, max_threads: i32) -> Result<Self, LibraryLoadReport> {
+        // The library's behavior for a zero or negative thread count is undefined,
+        // so reject it before EdgeHashGeneratorInit is ever reached.
+        if max_threads < 1 {
+            return Err(format!(
+                "Invalid max_threads value {}: must be at least 1",
+                max_threads
+            )
+            .into());
+        }
+
         #[cfg(photodna_no_sdk)]
         {
             let _ = (library_dir, max_threads); // Suppress unused warnings
             Err(
                 "PhotoDNA SDK not available: PHOTODNA_SDK_ROOT was not set at build time. \
                  Please rebuild with PHOTODNA_SDK_ROOT environment variable set to the SDK directory."
-                    .to_string(),
+                    .to_string()
+                    .into(),
             )
         }
 
         #[cfg(not(photodna_no_sdk))]
         {
-            let lib_dir = library_dir.unwrap_or(PHOTODNA_LIB_DIR);
             let lib_filename = get_library_filename();
-            let lib_path = format!("{}/{}", lib_dir, lib_filename);
+            let mut attempts = Vec::new();
+            let mut loaded = None;
 
-            unsafe {
-                // SAFETY: Library loading via libloading. The library path has been
+            for lib_dir in Self::library_dirs(library_dir) {
+                let lib_path = Path::new(&lib_dir).join(&lib_filename);
+                // SAFETY: Library loading via libloading. The default path has been
                 // validated at build time (PHOTODNA_LIB_DIR from build.rs).
-                let library = libloading::Library::new(&lib_path)
-                    .map_err(|e| format!("Failed to load library '{}': {}", lib_path, e))?;
+                match unsafe { libloading::Library::new(&lib_path) } {
+                    Ok(library) => {
+                        loaded = Some((library, lib_dir, lib_path));
+                        break;
+                    }
+                    Err(e) => attempts.push((lib_path, e)),
+                }
+            }
+
+            let Some((library, lib_dir, lib_path)) = loaded else {
+                return Err(LibraryLoadReport {
+                    reason: "Failed to load library".to_string(),
+                    attempts,
+                });
+            };
 
+            unsafe {
                 // SAFETY: Symbol resolution from the loaded library. All symbols are
                 // required to exist in the PhotoDNA library per the SDK documentation.
                 // The function pointer types match the C header definitions exactly.
@@ -791,7 +1168,7 @@ impl EdgeHashGenerator {
                 let library_instance = fn_init(c_lib_dir.as_ptr(), max_threads);
 
                 if library_instance.is_null() {
-                    return Err("Failed to initialize PhotoDNA library".to_string());
+                    return Err("Failed to initialize PhotoDNA library".to_string().into());
                 }
 
                 // SAFETY: Transmuting Symbol<'a> to Symbol<'static>.
@@ -834,6 +1211,7 @@ impl EdgeHashGenerator {
 
                 Ok(Self {
                     _library: library,
+                    library_path: lib_path,
                     library_instance,
                     fn_release,
                     fn_get_error_number,
@@ -852,6 +1230,110 @@ impl EdgeHashGenerator {
         }
     }
 
+    /// Releases the library instance and unloads the library now.
+    ///
+    /// Dropping an `EdgeHashGenerator` does the same, but ignores any
+    /// failure to unload. Use `close` when the library must be gone at a
+    /// known point (for example before `fork`) or when unload failures
+    /// should be reported.
+    ///
+    /// The instance is released exactly once: `close` consumes the value,
+    /// so `Drop` does not run for it afterwards, and the handle is nulled
+    /// as it is released.
+    ///
+    /// # Errors
+    ///
+    /// Returns the loader's message if the library could not be unloaded.
+    /// The instance has already been released by then.
+    pub fn close(self) -> Result<(), String> {
+        let mut this = std::mem::ManuallyDrop::new(self);
+        let fn_release = *this.fn_release;
+        // SAFETY: The instance came from `EdgeHashGeneratorInit` and is
+        // released at most once.
+        release_instance(&mut this.library_instance, |instance| unsafe {
+            fn_release(instance)
+        });
+
+        // SAFETY: `this` is never used or dropped after these reads, so each
+        // owned field is moved out once. The remaining fields are function
+        // pointers with no drop glue.
+        let (library, library_path) = unsafe {
+            (
+                std::ptr::read(&this._library),
+                std::ptr::read(&this.library_path),
+            )
+        };
+
+        library
+            .close()
+            .map_err(|e| format!("Failed to unload {}: {}", library_path.display(), e))
+    }
+
+    /// Lists which expected symbols a library exports, without
+    /// initializing it.
+    ///
+    /// [`new`](Self::new) stops at the first missing symbol; this resolves
+    /// every one of [`EXPECTED_SYMBOLS`] so a partial or mismatched SDK can
+    /// be diagnosed in one go. `dir_or_path` is either the library file
+    /// itself or a directory containing the platform's library filename.
+    ///
+    /// The library is loaded to look up its symbols, which runs its
+    /// platform initializers, but `EdgeHashGeneratorInit` is never called.
+    /// It is unloaded again before returning.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`LibraryLoadReport`] if the library could not be loaded.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let report = EdgeHashGenerator::probe("/opt/photodna/clientlibrary")?;
+    /// for name in report.missing() {
+    ///     eprintln!("missing: {}", name);
+    /// }
+    /// ```
+    pub fn probe(dir_or_path: impl AsRef<Path>) -> Result<SymbolReport, LibraryLoadReport> {
+        let path = dir_or_path.as_ref();
+        let lib_path = if path.is_dir() {
+            path.join(get_library_filename())
+        } else {
+            path.to_path_buf()
+        };
+
+        // SAFETY: Loading the library runs its initializers, as `new` does;
+        // the caller chose the path.
+        let library = match unsafe { libloading::Library::new(&lib_path) } {
+            Ok(library) => library,
+            Err(e) => {
+                return Err(LibraryLoadReport {
+                    reason: "Failed to load library".to_string(),
+                    attempts: vec![(lib_path, e)],
+                })
+            }
+        };
+
+        Ok(SymbolReport::resolve_with(lib_path, |name| {
+            // SAFETY: The symbol is only looked up, never called, so its
+            // actual type does not matter.
+            unsafe { library.get::<*const c_void>(name.as_bytes()).is_ok() }
+        }))
+    }
+
+    /// Returns the directories to search for the library, in order.
+    #[cfg(not(photodna_no_sdk))]
+    fn library_dirs(library_dir: Option<&str>) -> Vec<String> {
+        vec![library_dir.unwrap_or(PHOTODNA_LIB_DIR).to_string()]
+    }
+
+    /// Returns the path the dynamic library was loaded from.
+    ///
+    /// Useful for confirming which SDK copy is active when several are
+    /// installed.
+    pub fn library_path(&self) -> &Path {
+        &self.library_path
+    }
+
     /// Returns the raw library instance handle.
     ///
     /// # Safety
@@ -1085,12 +1567,28 @@ impl EdgeHashGenerator {
 
 #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
 impl Drop for EdgeHashGenerator {
+    /// Releases the instance and unloads the library, ignoring failures.
+    /// Not run for generators consumed by [`close`](Self::close).
     fn drop(&mut self) {
-        unsafe {
-            // Release the library instance
-            (self.fn_release)(self.library_instance);
-            // The library is automatically unloaded when _library is dropped
-        }
+        let fn_release = &self.fn_release;
+        // SAFETY: The instance came from `EdgeHashGeneratorInit`, and
+        // `release_instance` skips it if it was already released.
+        release_instance(&mut self.library_instance, |instance| unsafe {
+            fn_release(instance)
+        });
+        // The library is automatically unloaded when _library is dropped
+    }
+}
+
+/// Passes `*instance` to `release` unless it is null, then nulls it.
+///
+/// Shared by [`EdgeHashGenerator::close`] and `Drop` so an instance is never
+/// released twice.
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+fn release_instance(instance: &mut *mut c_void, release: impl FnOnce(*mut c_void)) {
+    let instance = std::mem::replace(instance, std::ptr::null_mut());
+    if !instance.is_null() {
+        release(instance);
     }
 }
 
@@ -1239,6 +1737,79 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_options_builder() {
+        assert_eq!(Options::new().build(), Ok(PhotoDna_Default));
+        assert_eq!(
+            Options::new()
+                .pixel_format(PhotoDna_Yuv420p)
+                .hash_format(PhotoDna_HashFormatEdgeV2Base64)
+                .no_rotate_flip(true)
+                .verbose(true)
+                .check_memory(true)
+                .check_memory(false)
+                .build(),
+            Ok(PhotoDna_Yuv420p
+                | PhotoDna_HashFormatEdgeV2Base64
+                | PhotoDna_NoRotateFlip
+                | PhotoDna_Verbose)
+        );
+        // Aliases of the same layout are not a conflict.
+        assert_eq!(
+            Options::new()
+                .pixel_format(PhotoDna_Rgba)
+                .pixel_format(PhotoDna_Bgra)
+                .build(),
+            Ok(PhotoDna_Rgba)
+        );
+    }
+
+    #[test]
+    fn test_options_builder_rejects_conflicts() {
+        assert_eq!(
+            Options::new()
+                .pixel_format(PhotoDna_Rgba)
+                .pixel_format(PhotoDna_Argb)
+                .build(),
+            Err(OptionsError::ConflictingPixelFormats(
+                PhotoDna_Rgba,
+                PhotoDna_Argb
+            ))
+        );
+        assert_eq!(
+            Options::new()
+                .hash_format(PhotoDna_HashFormatEdgeV2)
+                .hash_format(PhotoDna_HashFormatEdgeV2Base64)
+                .build(),
+            Err(OptionsError::ConflictingHashFormats(
+                PhotoDna_HashFormatEdgeV2,
+                PhotoDna_HashFormatEdgeV2Base64
+            ))
+        );
+        assert_eq!(
+            Options::new()
+                .pixel_format(PhotoDna_Rgba | PhotoDna_Yuv420p)
+                .build(),
+            Err(OptionsError::InvalidPixelFormat(0x900))
+        );
+        assert_eq!(
+            Options::new().hash_format(PhotoDna_RemoveBorder).build(),
+            Err(OptionsError::InvalidHashFormat(PhotoDna_RemoveBorder))
+        );
+
+        // The first problem is reported.
+        let err = Options::new()
+            .pixel_format(PhotoDna_Grey8)
+            .pixel_format(PhotoDna_Cmyk)
+            .hash_format(0x1234)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "conflicting pixel formats 0x0400 and 0x0300"
+        );
+    }
+
     #[test]
     fn test_constants() {
         assert_eq!(PHOTODNA_HASH_SIZE_EDGE_V2, 924);
@@ -1246,6 +1817,76 @@ mod tests {
         assert_eq!(PhotoDna_EdgeV2 as usize, PHOTODNA_HASH_SIZE_EDGE_V2);
     }
 
+    #[test]
+    #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+    fn test_new_rejects_invalid_max_threads() {
+        let err = EdgeHashGenerator::new(None, 0)
+            .err()
+            .expect("max_threads=0 must fail");
+        assert!(err.contains("max_threads"));
+
+        assert!(EdgeHashGenerator::new(None, -4).is_err());
+    }
+
+    #[test]
+    #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+    fn test_release_instance_runs_once() {
+        let mut value = 0u8;
+        let mut instance = (&mut value as *mut u8).cast::<c_void>();
+        let mut releases = 0;
+
+        // As in `close` followed by `Drop`.
+        release_instance(&mut instance, |_| releases += 1);
+        release_instance(&mut instance, |_| releases += 1);
+
+        assert_eq!(releases, 1);
+        assert!(instance.is_null());
+    }
+
+    #[test]
+    #[cfg(all(
+        any(target_os = "windows", target_os = "linux", target_os = "macos"),
+        not(photodna_no_sdk)
+    ))]
+    fn test_load_reports_tried_paths() {
+        let dir = "/nonexistent/photodna-sys-test";
+        let report = EdgeHashGenerator::load(Some(dir), 1)
+            .err()
+            .expect("loading from a missing directory must fail");
+
+        let expected = Path::new(dir).join(get_library_filename());
+        let tried: Vec<&Path> = report.attempts().iter().map(|(p, _)| p.as_path()).collect();
+        assert_eq!(tried, vec![expected.as_path()]);
+        assert!(report.to_string().contains(&*expected.to_string_lossy()));
+    }
+
+    #[test]
+    #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+    fn test_symbol_report() {
+        let report = SymbolReport::resolve_with(PathBuf::from("old.so"), |name| {
+            !name.starts_with("PhotoDnaEdgeHashBorder")
+        });
+
+        let names: Vec<&str> = report.symbols().iter().map(|&(name, _)| name).collect();
+        assert_eq!(names, EXPECTED_SYMBOLS);
+        assert_eq!(report.library_path(), Path::new("old.so"));
+        assert_eq!(report.is_found("GetErrorNumber"), Some(true));
+        assert_eq!(report.is_found("PhotoDnaEdgeHashBorderSub"), Some(false));
+        assert_eq!(report.is_found("NotAPhotoDnaSymbol"), None);
+        assert_eq!(
+            report.missing().collect::<Vec<_>>(),
+            ["PhotoDnaEdgeHashBorder", "PhotoDnaEdgeHashBorderSub"]
+        );
+        assert!(!report.is_complete());
+        assert!(report
+            .to_string()
+            .contains("PhotoDnaEdgeHashBorder: missing"));
+
+        let dir = "/nonexistent/photodna-sys-test";
+        let err = EdgeHashGenerator::probe(dir).expect_err("probing a missing library must fail");
+        assert_eq!(err.attempts().len(), 1);
+    }
+
     #[test]
     #[cfg(all(
         any(target_os = "windows", target_os = "linux", target_os = "macos"),
@@ -0,0 +1,116 @@
+//! Distance kernels, one query against many stored hashes.
+//!
+//! Run with `cargo bench -p photodna --bench distance`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use photodna::{matching, Hash, PreparedHash, HASH_SIZE};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::Duration;
+
+/// Number of stored hashes each query is compared against.
+const CANDIDATES: usize = 10_000;
+
+/// Returns `count` full hashes of uniformly random bytes.
+fn random_hashes(count: usize, seed: u64) -> Vec<Hash> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| {
+            let mut bytes = [0u8; HASH_SIZE];
+            rng.fill(&mut bytes[..]);
+            Hash::new(bytes)
+        })
+        .collect()
+}
+
+/// Byte-by-byte distance with a `u64` accumulator, as a baseline.
+fn scalar_distance(a: &Hash, b: &Hash) -> f64 {
+    let sum: u64 = a
+        .as_bytes()
+        .iter()
+        .zip(b.as_bytes())
+        .map(|(&x, &y)| u64::from(x.abs_diff(y)).pow(2))
+        .sum();
+    (sum as f64).sqrt()
+}
+
+fn prepared_vs_direct(c: &mut Criterion) {
+    let query = random_hashes(1, 1)[0];
+    let candidates = random_hashes(CANDIDATES, 2);
+    let prepared_query = PreparedHash::new(query);
+    let prepared: Vec<PreparedHash> = candidates.iter().copied().map(PreparedHash::new).collect();
+
+    let mut group = c.benchmark_group("one_to_10k");
+    group.bench_function("scalar_u64", |b| {
+        b.iter(|| {
+            for candidate in &candidates {
+                black_box(scalar_distance(black_box(&query), candidate));
+            }
+        })
+    });
+    group.bench_function("hash_distance", |b| {
+        b.iter(|| {
+            for candidate in &candidates {
+                black_box(black_box(&query).distance(candidate));
+            }
+        })
+    });
+    group.bench_function("prepared_distance_to", |b| {
+        b.iter(|| {
+            for candidate in &prepared {
+                black_box(candidate.distance_to(black_box(&prepared_query)));
+            }
+        })
+    });
+    group.bench_function("prepare_query_and_scan", |b| {
+        b.iter_batched(
+            || query,
+            |query| {
+                let query = PreparedHash::new(query);
+                for candidate in &prepared {
+                    black_box(candidate.distance_to(&query));
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn one_to_many(c: &mut Criterion) {
+    let query = random_hashes(1, 1)[0];
+    let candidates = random_hashes(CANDIDATES, 2);
+    let mut out = vec![0.0; CANDIDATES];
+
+    let mut group = c.benchmark_group("one_to_many_10k");
+    group.bench_function("naive_map_collect", |b| {
+        b.iter(|| {
+            let distances: Vec<f64> = candidates
+                .iter()
+                .map(|candidate| black_box(&query).distance(candidate))
+                .collect();
+            black_box(distances)
+        })
+    });
+    group.bench_function("distances_one_to_many", |b| {
+        b.iter(|| {
+            matching::distances_one_to_many(black_box(&query), &candidates, &mut out);
+            black_box(&out);
+        })
+    });
+    group.finish();
+}
+
+fn config() -> Criterion {
+    Criterion::default()
+        .sample_size(20)
+        .warm_up_time(Duration::from_secs(1))
+        .measurement_time(Duration::from_secs(3))
+}
+
+criterion_group! {
+    name = benches;
+    config = config();
+    targets = prepared_vs_direct, one_to_many
+}
+criterion_main!(benches);
@@ -0,0 +1,171 @@
+//! Store scans over random hashes.
+//!
+//! Run with `cargo bench -p photodna --bench store`. Besides the timings,
+//! this prints how many entries the prefilter keeps. Add `--features mmap`
+//! to also time [`MmapHashStore`](photodna::MmapHashStore) scans.
+
+use criterion::{black_box, criterion_group, Criterion};
+use photodna::{ColumnarHashStore, Hash, HashStore, HASH_SIZE};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::Duration;
+
+/// Number of stored hashes in the scanned stores.
+const ENTRIES: usize = 100_000;
+
+/// Returns `count` full hashes of uniformly random bytes.
+fn random_hashes(count: usize, rng: &mut StdRng) -> Vec<Hash> {
+    (0..count)
+        .map(|_| {
+            let mut bytes = [0u8; HASH_SIZE];
+            rng.fill(&mut bytes[..]);
+            Hash::new(bytes)
+        })
+        .collect()
+}
+
+/// Moves every byte of `hash` up or down by `step`, at random.
+fn perturb(hash: &Hash, step: u8, rng: &mut StdRng) -> Hash {
+    let mut bytes = [0u8; HASH_SIZE];
+    for (out, &byte) in bytes.iter_mut().zip(hash.as_bytes()) {
+        *out = if rng.gen() {
+            byte.saturating_add(step)
+        } else {
+            byte.saturating_sub(step)
+        };
+    }
+    Hash::new(bytes)
+}
+
+fn store_of(hashes: &[Hash], mut store: HashStore) -> HashStore {
+    for (i, hash) in hashes.iter().enumerate() {
+        store.insert(i.to_string(), *hash);
+    }
+    store
+}
+
+/// Prints how many unrelated entries the prefilter keeps, and how often it
+/// keeps a near-duplicate at a given distance.
+fn report_prefilter() {
+    let mut rng = StdRng::seed_from_u64(1597);
+    let store = store_of(
+        &random_hashes(ENTRIES, &mut rng),
+        HashStore::with_prefilter(),
+    );
+    let queries = random_hashes(100, &mut rng);
+    let kept: usize = queries
+        .iter()
+        .map(|query| store.find_all(query, f64::INFINITY).len())
+        .sum();
+    println!(
+        "prefilter: kept {:.4}% of {} random entries per random query",
+        kept as f64 * 100.0 / (queries.len() * ENTRIES) as f64,
+        ENTRIES
+    );
+
+    let originals = random_hashes(1000, &mut rng);
+    let store = store_of(&originals, HashStore::with_prefilter());
+    for step in [14, 28, 42, 53, 66] {
+        let mut distance = 0.0;
+        let mut found = 0;
+        for (i, original) in originals.iter().enumerate() {
+            let query = perturb(original, step, &mut rng);
+            distance += original.distance(&query);
+            let id = i.to_string();
+            if store
+                .find_all(&query, f64::INFINITY)
+                .iter()
+                .any(|&(hit, _)| hit == id)
+            {
+                found += 1;
+            }
+        }
+        println!(
+            "prefilter: near-duplicates at mean distance {:.0} kept {:.1}% of the time",
+            distance / originals.len() as f64,
+            found as f64 * 100.0 / originals.len() as f64
+        );
+    }
+}
+
+fn prefilter(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(1);
+    let hashes = random_hashes(ENTRIES, &mut rng);
+    let query = perturb(&hashes[ENTRIES / 2], 28, &mut rng);
+    let plain = store_of(&hashes, HashStore::new());
+    let filtered = store_of(&hashes, HashStore::with_prefilter());
+
+    let mut group = c.benchmark_group("find_match_100k");
+    group.bench_function("unfiltered", |b| {
+        b.iter(|| black_box(plain.find_match(black_box(&query), 1000.0)))
+    });
+    group.bench_function("prefilter", |b| {
+        b.iter(|| black_box(filtered.find_match(black_box(&query), 1000.0)))
+    });
+    group.finish();
+}
+
+fn columnar(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(2);
+    let hashes = random_hashes(ENTRIES, &mut rng);
+    let query = perturb(&hashes[ENTRIES / 2], 28, &mut rng);
+    let rows = store_of(&hashes, HashStore::new());
+    let mut columns = ColumnarHashStore::new();
+    for (i, hash) in hashes.iter().enumerate() {
+        columns.insert(i.to_string(), *hash);
+    }
+
+    let mut group = c.benchmark_group("find_match_100k");
+    group.bench_function("row", |b| {
+        b.iter(|| black_box(rows.find_match(black_box(&query), 1000.0)))
+    });
+    group.bench_function("columnar", |b| {
+        b.iter(|| black_box(columns.find_match(black_box(&query), 1000.0)))
+    });
+    group.finish();
+}
+
+#[cfg(feature = "mmap")]
+fn mmap(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(3);
+    let hashes = random_hashes(ENTRIES, &mut rng);
+    let query = perturb(&hashes[ENTRIES / 2], 28, &mut rng);
+    let path = std::env::temp_dir().join(format!("photodna-bench-{}.bin", std::process::id()));
+    let file = std::fs::File::create(&path).unwrap();
+    store_of(&hashes, HashStore::new())
+        .save(std::io::BufWriter::new(file))
+        .unwrap();
+    let mapped = HashStore::open_mmap(&path).unwrap();
+
+    let mut group = c.benchmark_group("find_match_100k");
+    // The file was just written, so it is in the page cache.
+    group.bench_function("mmap_warm", |b| {
+        b.iter(|| black_box(mapped.find_match(black_box(&query), 1000.0)))
+    });
+    group.finish();
+
+    drop(mapped);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(not(feature = "mmap"))]
+fn mmap(_: &mut Criterion) {}
+
+fn config() -> Criterion {
+    Criterion::default()
+        .sample_size(10)
+        .warm_up_time(Duration::from_secs(1))
+        .measurement_time(Duration::from_secs(3))
+}
+
+criterion_group! {
+    name = benches;
+    config = config();
+    targets = prefilter, columnar, mmap
+}
+
+fn main() {
+    report_prefilter();
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+}
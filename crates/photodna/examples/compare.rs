@@ -0,0 +1,69 @@
+//! Compares two images with PhotoDNA
+//!
+//! This example hashes two image files and reports the distance between the
+//! hashes and whether they match at the given threshold.
+//!
+//! Run with:
+//! ```bash
+//! PHOTODNA_SDK_ROOT=/path/to/PhotoDNA.EdgeHashGeneration-1.05.001 \
+//!     cargo run --example compare --features image -- a.jpg b.png [threshold]
+//! ```
+//!
+//! Exit codes: 0 if the images match, 1 if they don't, 2 on any error.
+
+use photodna::{
+    Generator, GeneratorOptions, Hash, HashOptions, MatchVerdict, DEFAULT_MATCH_THRESHOLD,
+};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (first, second, threshold) = match args.as_slice() {
+        [a, b] => (a, b, DEFAULT_MATCH_THRESHOLD),
+        [a, b, t] => match t.parse() {
+            Ok(t) => (a, b, t),
+            Err(_) => {
+                eprintln!("✗ Invalid threshold: {}", t);
+                return ExitCode::from(2);
+            }
+        },
+        _ => {
+            eprintln!("Usage: compare <image-a> <image-b> [threshold]");
+            return ExitCode::from(2);
+        }
+    };
+
+    let generator = match Generator::new(GeneratorOptions::default()) {
+        Ok(generator) => generator,
+        Err(e) => {
+            eprintln!("✗ Failed to load library: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let hash = |path: &str| -> Option<Hash> {
+        match generator.compute_hash_from_path(path, HashOptions::default()) {
+            Ok(hash) => Some(hash),
+            Err(e) => {
+                eprintln!("✗ Failed to hash {}: {}", path, e);
+                None
+            }
+        }
+    };
+    let (Some(a), Some(b)) = (hash(first), hash(second)) else {
+        return ExitCode::from(2);
+    };
+
+    println!("Distance:  {:.1}", a.distance(&b));
+    println!("Threshold: {:.1}", threshold);
+
+    match a.compare(&b, threshold) {
+        MatchVerdict::Identical => println!("✓ Identical"),
+        MatchVerdict::Match(_) => println!("✓ Match"),
+        MatchVerdict::NoMatch(_) => {
+            println!("✗ No match");
+            return ExitCode::from(1);
+        }
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,132 @@
+//! Hashing `ndarray` arrays (requires the `ndarray` feature).
+//!
+//! Arrays are laid out as `(height, width, channels)`, the convention used
+//! by most imaging and scientific Python-style code.
+
+use crate::{Generator, Hash, HashOptions, PhotoDnaError, PixelFormat, Result};
+use ndarray::ArrayView3;
+
+impl Generator {
+    /// Computes a PhotoDNA hash from a `(height, width, channels)` array.
+    ///
+    /// The channel axis must match [`HashOptions::pixel_format`], e.g. 3 for
+    /// [`PixelFormat::Rgb`] or 1 for [`PixelFormat::Gray8`]. Arrays in
+    /// standard (row-major, contiguous) layout are hashed in place; any
+    /// other layout, such as a transposed or sliced view, is copied into
+    /// standard layout first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PhotoDnaError::ArrayLayout`] if the channel count does not
+    /// match the pixel format, if the format is planar
+    /// ([`PixelFormat::Yuv420p`]), or if a dimension does not fit in `u32`.
+    /// Otherwise returns any error from [`compute_hash`](Self::compute_hash).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use ndarray::Array3;
+    ///
+    /// let pixels = Array3::<u8>::zeros((480, 640, 3));
+    /// let hash = generator.compute_hash_ndarray(pixels.view(), HashOptions::default())?;
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "ndarray")))]
+    pub fn compute_hash_ndarray(
+        &self,
+        arr: ArrayView3<'_, u8>,
+        options: HashOptions,
+    ) -> Result<Hash> {
+        let (height, width, channels) = arr.dim();
+        let format = options.pixel_format;
+
+        if format == PixelFormat::Yuv420p {
+            return Err(PhotoDnaError::ArrayLayout(
+                "planar Yuv420p data cannot be read from a (height, width, channels) array".into(),
+            ));
+        }
+        if channels != format.bytes_per_pixel() {
+            return Err(PhotoDnaError::ArrayLayout(format!(
+                "{:?} needs {} channels, array has {}",
+                format,
+                format.bytes_per_pixel(),
+                channels
+            )));
+        }
+        let (Ok(width), Ok(height)) = (u32::try_from(width), u32::try_from(height)) else {
+            return Err(PhotoDnaError::ArrayLayout(format!(
+                "{}x{} array is too large",
+                width, height
+            )));
+        };
+
+        let arr = arr.as_standard_layout();
+        let data = arr
+            .as_slice()
+            .expect("standard layout arrays are contiguous");
+        self.compute_hash(data, width, height, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::mock::MockBackend;
+    use ndarray::{s, Array3, Axis};
+
+    fn gradient(height: usize, width: usize) -> Array3<u8> {
+        Array3::from_shape_fn((height, width, 3), |(y, x, c)| (x + y * 2 + c * 40) as u8)
+    }
+
+    #[test]
+    fn test_compute_hash_ndarray() {
+        let generator = Generator::from_backend(MockBackend {
+            echo_input: true,
+            ..Default::default()
+        });
+        let arr = gradient(64, 64);
+        let options = HashOptions::new();
+
+        let hash = generator.compute_hash_ndarray(arr.view(), options).unwrap();
+        let expected = generator
+            .compute_hash(arr.as_slice().unwrap(), 64, 64, options)
+            .unwrap();
+        assert_eq!(hash, expected);
+
+        // A transposed view is copied into standard layout before hashing.
+        let transposed = gradient(64, 64).permuted_axes([1, 0, 2]);
+        let copied = transposed.as_standard_layout().to_owned();
+        assert_eq!(
+            generator
+                .compute_hash_ndarray(transposed.view(), options)
+                .unwrap(),
+            generator
+                .compute_hash_ndarray(copied.view(), options)
+                .unwrap()
+        );
+
+        // So is a view with the channel axis reversed.
+        let bgr_view = arr.slice(s![.., .., ..;-1]);
+        assert!(generator
+            .compute_hash_ndarray(bgr_view, options.pixel_format(PixelFormat::Bgr))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_compute_hash_ndarray_rejects_channel_mismatch() {
+        let generator = Generator::from_backend(MockBackend::default());
+        let arr = gradient(64, 64);
+
+        let rgba = HashOptions::new().pixel_format(PixelFormat::Rgba);
+        assert!(matches!(
+            generator.compute_hash_ndarray(arr.view(), rgba),
+            Err(PhotoDnaError::ArrayLayout(_))
+        ));
+
+        let yuv = HashOptions::new().pixel_format(PixelFormat::Yuv420p);
+        let gray = arr.index_axis(Axis(2), 0).insert_axis(Axis(2));
+        assert!(matches!(
+            generator.compute_hash_ndarray(gray, yuv),
+            Err(PhotoDnaError::ArrayLayout(_))
+        ));
+    }
+}
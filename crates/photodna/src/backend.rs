@@ -0,0 +1,709 @@
+//! Backend abstraction over the PhotoDNA library.
+//!
+//! [`Generator`](crate::Generator) talks to the library through the
+//! [`Backend`] trait rather than calling `photodna_sys` directly. The native
+//! implementation simply forwards to [`photodna_sys::EdgeHashGenerator`];
+//! the `stub` feature and unit tests substitute fixture-driven backends so
+//! the safe layer can be exercised without the proprietary SDK.
+
+use photodna_sys::{self as sys, HashResult, PhotoDnaOptions};
+use std::ffi::c_void;
+use std::path::Path;
+
+/// The operations the safe layer needs from a PhotoDNA implementation.
+///
+/// Method contracts mirror the corresponding `photodna_sys` functions,
+/// including the safety requirements of the `unsafe` hashing entry points.
+pub(crate) trait Backend {
+    /// Returns the raw library instance pointer (null if there is none).
+    fn raw_instance(&self) -> *mut c_void;
+
+    /// Returns the path of the loaded dynamic library, if there is one.
+    fn library_path(&self) -> Option<&Path>;
+
+    /// Returns the last error number reported by the library.
+    fn error_number(&self) -> i32;
+
+    /// Returns the library's description of an error code.
+    fn error_string(&self, code: i32) -> Option<&str>;
+
+    /// Returns the packed library version.
+    fn library_version(&self) -> i32;
+
+    /// Returns the major library version.
+    fn library_version_major(&self) -> i32;
+
+    /// Returns the minor library version.
+    fn library_version_minor(&self) -> i32;
+
+    /// Returns the patch library version.
+    fn library_version_patch(&self) -> i32;
+
+    /// Returns the library version as text.
+    fn library_version_text(&self) -> Option<&str>;
+
+    /// See [`sys::EdgeHashGenerator::photo_dna_edge_hash`].
+    ///
+    /// # Safety
+    ///
+    /// Same contract as the sys function.
+    unsafe fn edge_hash(
+        &self,
+        image_data: *const u8,
+        hash_value: *mut u8,
+        width: i32,
+        height: i32,
+        stride: i32,
+        options: PhotoDnaOptions,
+    ) -> i32;
+
+    /// See [`sys::EdgeHashGenerator::photo_dna_edge_hash_border`].
+    ///
+    /// # Safety
+    ///
+    /// Same contract as the sys function.
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn edge_hash_border(
+        &self,
+        image_data: *const u8,
+        hash_results: *mut HashResult,
+        max_hash_count: i32,
+        width: i32,
+        height: i32,
+        stride: i32,
+        options: PhotoDnaOptions,
+    ) -> i32;
+
+    /// See [`sys::EdgeHashGenerator::photo_dna_edge_hash_border_sub`].
+    ///
+    /// # Safety
+    ///
+    /// Same contract as the sys function.
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn edge_hash_border_sub(
+        &self,
+        image_data: *const u8,
+        hash_results: *mut HashResult,
+        max_hash_count: i32,
+        width: i32,
+        height: i32,
+        stride: i32,
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        options: PhotoDnaOptions,
+    ) -> i32;
+
+    /// See [`sys::EdgeHashGenerator::photo_dna_edge_hash_sub`].
+    ///
+    /// # Safety
+    ///
+    /// Same contract as the sys function.
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn edge_hash_sub(
+        &self,
+        image_data: *const u8,
+        hash_value: *mut u8,
+        width: i32,
+        height: i32,
+        stride: i32,
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        options: PhotoDnaOptions,
+    ) -> i32;
+}
+
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+impl Backend for sys::EdgeHashGenerator {
+    fn raw_instance(&self) -> *mut c_void {
+        sys::EdgeHashGenerator::raw_instance(self)
+    }
+
+    fn library_path(&self) -> Option<&Path> {
+        Some(sys::EdgeHashGenerator::library_path(self))
+    }
+
+    fn error_number(&self) -> i32 {
+        self.get_error_number()
+    }
+
+    fn error_string(&self, code: i32) -> Option<&str> {
+        self.get_error_string(code)
+    }
+
+    fn library_version(&self) -> i32 {
+        sys::EdgeHashGenerator::library_version(self)
+    }
+
+    fn library_version_major(&self) -> i32 {
+        sys::EdgeHashGenerator::library_version_major(self)
+    }
+
+    fn library_version_minor(&self) -> i32 {
+        sys::EdgeHashGenerator::library_version_minor(self)
+    }
+
+    fn library_version_patch(&self) -> i32 {
+        sys::EdgeHashGenerator::library_version_patch(self)
+    }
+
+    fn library_version_text(&self) -> Option<&str> {
+        sys::EdgeHashGenerator::library_version_text(self)
+    }
+
+    unsafe fn edge_hash(
+        &self,
+        image_data: *const u8,
+        hash_value: *mut u8,
+        width: i32,
+        height: i32,
+        stride: i32,
+        options: PhotoDnaOptions,
+    ) -> i32 {
+        // SAFETY: Caller upholds the sys function's contract.
+        unsafe { self.photo_dna_edge_hash(image_data, hash_value, width, height, stride, options) }
+    }
+
+    unsafe fn edge_hash_border(
+        &self,
+        image_data: *const u8,
+        hash_results: *mut HashResult,
+        max_hash_count: i32,
+        width: i32,
+        height: i32,
+        stride: i32,
+        options: PhotoDnaOptions,
+    ) -> i32 {
+        // SAFETY: Caller upholds the sys function's contract.
+        unsafe {
+            self.photo_dna_edge_hash_border(
+                image_data,
+                hash_results,
+                max_hash_count,
+                width,
+                height,
+                stride,
+                options,
+            )
+        }
+    }
+
+    unsafe fn edge_hash_border_sub(
+        &self,
+        image_data: *const u8,
+        hash_results: *mut HashResult,
+        max_hash_count: i32,
+        width: i32,
+        height: i32,
+        stride: i32,
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        options: PhotoDnaOptions,
+    ) -> i32 {
+        // SAFETY: Caller upholds the sys function's contract.
+        unsafe {
+            self.photo_dna_edge_hash_border_sub(
+                image_data,
+                hash_results,
+                max_hash_count,
+                width,
+                height,
+                stride,
+                x,
+                y,
+                w,
+                h,
+                options,
+            )
+        }
+    }
+
+    unsafe fn edge_hash_sub(
+        &self,
+        image_data: *const u8,
+        hash_value: *mut u8,
+        width: i32,
+        height: i32,
+        stride: i32,
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        options: PhotoDnaOptions,
+    ) -> i32 {
+        // SAFETY: Caller upholds the sys function's contract.
+        unsafe {
+            self.photo_dna_edge_hash_sub(
+                image_data, hash_value, width, height, stride, x, y, w, h, options,
+            )
+        }
+    }
+}
+
+/// Backend returning fixture hashes, used by [`Generator::new_stub`] and
+/// [`Generator::new_content_stub`].
+///
+/// [`Generator::new_stub`]: crate::Generator::new_stub
+/// [`Generator::new_content_stub`]: crate::Generator::new_content_stub
+#[cfg(feature = "stub")]
+pub(crate) struct StubBackend {
+    /// The hash written for every successful call, or `None` to derive it
+    /// from the pixels.
+    hash: Option<crate::Hash>,
+}
+
+#[cfg(feature = "stub")]
+impl StubBackend {
+    /// Creates a stub that answers every request with a fixed fixture hash.
+    pub(crate) fn new() -> Self {
+        Self {
+            hash: Some(crate::test_utils::fixtures::sample_hash_a()),
+        }
+    }
+
+    /// Creates a stub whose hashes are derived from the pixels hashed.
+    pub(crate) fn content() -> Self {
+        Self { hash: None }
+    }
+
+    /// Writes the hash for an image (or the `region` of it) to `out`.
+    ///
+    /// # Safety
+    ///
+    /// `image_data` must hold the image described by the other arguments,
+    /// and `region` must lie within it.
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn write_hash(
+        &self,
+        image_data: *const u8,
+        out: &mut [u8],
+        width: i32,
+        _height: i32,
+        stride: i32,
+        region: (i32, i32, i32, i32),
+        options: PhotoDnaOptions,
+    ) {
+        if let Some(hash) = &self.hash {
+            out[..hash.len()].copy_from_slice(hash.as_bytes());
+            return;
+        }
+
+        // Only the luma plane of YUV420P images is read.
+        let channels = match crate::PixelFormat::from_options(options) {
+            crate::PixelFormat::Yuv420p => 1,
+            format => format.bytes_per_pixel(),
+        };
+        let row_len = width as usize * channels;
+        let stride = if stride == 0 {
+            row_len
+        } else {
+            stride as usize
+        };
+        let (x, y, w, h) = region;
+
+        // FNV-1a over the dimensions and the region's pixels.
+        let mut seed = 0xcbf2_9ce4_8422_2325u64;
+        let mut mix = |bytes: &[u8]| {
+            for &b in bytes {
+                seed = (seed ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3);
+            }
+        };
+        mix(&w.to_le_bytes());
+        mix(&h.to_le_bytes());
+        for row in y..y + h {
+            let start = row as usize * stride + x as usize * channels;
+            // SAFETY: The caller guarantees the region lies within the image.
+            let pixels =
+                unsafe { std::slice::from_raw_parts(image_data.add(start), w as usize * channels) };
+            mix(pixels);
+        }
+
+        // Expand the seed with SplitMix64.
+        for chunk in out[..crate::HASH_SIZE].chunks_mut(8) {
+            seed = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = seed;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^= z >> 31;
+            chunk.copy_from_slice(&z.to_le_bytes()[..chunk.len()]);
+        }
+    }
+}
+
+#[cfg(feature = "stub")]
+impl Backend for StubBackend {
+    fn raw_instance(&self) -> *mut c_void {
+        std::ptr::null_mut()
+    }
+
+    fn library_path(&self) -> Option<&Path> {
+        None
+    }
+
+    fn error_number(&self) -> i32 {
+        0
+    }
+
+    fn error_string(&self, code: i32) -> Option<&str> {
+        Some(sys::error_code_description(code))
+    }
+
+    fn library_version(&self) -> i32 {
+        (self.library_version_major() << 16) | self.library_version_minor()
+    }
+
+    fn library_version_major(&self) -> i32 {
+        1
+    }
+
+    fn library_version_minor(&self) -> i32 {
+        5
+    }
+
+    fn library_version_patch(&self) -> i32 {
+        0
+    }
+
+    fn library_version_text(&self) -> Option<&str> {
+        Some("stub (not PhotoDNA)")
+    }
+
+    unsafe fn edge_hash(
+        &self,
+        image_data: *const u8,
+        hash_value: *mut u8,
+        width: i32,
+        height: i32,
+        stride: i32,
+        options: PhotoDnaOptions,
+    ) -> i32 {
+        // SAFETY: Forwarded with the caller's guarantees.
+        unsafe {
+            self.edge_hash_sub(
+                image_data, hash_value, width, height, stride, 0, 0, width, height, options,
+            )
+        }
+    }
+
+    unsafe fn edge_hash_border(
+        &self,
+        image_data: *const u8,
+        hash_results: *mut HashResult,
+        max_hash_count: i32,
+        width: i32,
+        height: i32,
+        stride: i32,
+        options: PhotoDnaOptions,
+    ) -> i32 {
+        // SAFETY: Forwarded with the caller's guarantees.
+        unsafe {
+            self.edge_hash_border_sub(
+                image_data,
+                hash_results,
+                max_hash_count,
+                width,
+                height,
+                stride,
+                0,
+                0,
+                width,
+                height,
+                options,
+            )
+        }
+    }
+
+    unsafe fn edge_hash_border_sub(
+        &self,
+        image_data: *const u8,
+        hash_results: *mut HashResult,
+        max_hash_count: i32,
+        width: i32,
+        height: i32,
+        stride: i32,
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        options: PhotoDnaOptions,
+    ) -> i32 {
+        if max_hash_count < 1 {
+            return sys::PhotoDna_ErrorBadArgument;
+        }
+        // SAFETY: Caller guarantees at least `max_hash_count` entries.
+        let first = unsafe { &mut *hash_results };
+        first.result = 1;
+        // SAFETY: Forwarded with the caller's guarantees.
+        unsafe {
+            self.write_hash(
+                image_data,
+                &mut first.hash,
+                width,
+                height,
+                stride,
+                (x, y, w, h),
+                options,
+            );
+        }
+        1
+    }
+
+    unsafe fn edge_hash_sub(
+        &self,
+        image_data: *const u8,
+        hash_value: *mut u8,
+        width: i32,
+        height: i32,
+        stride: i32,
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        options: PhotoDnaOptions,
+    ) -> i32 {
+        // SAFETY: Caller guarantees `hash_value` is writable for a full
+        // hash and the image and region are valid.
+        unsafe {
+            let out = std::slice::from_raw_parts_mut(hash_value, crate::HASH_SIZE);
+            self.write_hash(
+                image_data,
+                out,
+                width,
+                height,
+                stride,
+                (x, y, w, h),
+                options,
+            );
+        }
+        0
+    }
+}
+
+/// A scriptable backend for unit tests.
+#[cfg(test)]
+pub(crate) mod mock {
+    use super::*;
+    use crate::{Region, HASH_SIZE};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::{Arc, Barrier, Mutex};
+
+    /// Mock backend returning configured values instead of calling the SDK.
+    ///
+    /// Successful hash calls fill the output with `fill_byte`, or with the
+    /// start of the image data when `echo_input` is set.
+    pub(crate) struct MockBackend {
+        /// (major, minor, patch) reported by the version functions.
+        pub version: (i32, i32, i32),
+        /// Value returned by every hashing call.
+        pub hash_return: i32,
+        /// Value reported as the library's last error number.
+        pub error_number: i32,
+        /// Byte written to the hash output on success.
+        pub fill_byte: u8,
+        /// Copy the first [`HASH_SIZE`] bytes of the image into the hash, so
+        /// tests can observe the pixels the library receives.
+        pub echo_input: bool,
+        /// Panic inside every hashing call, as a misbehaving library might.
+        pub panic_on_hash: bool,
+        /// Number of upcoming hashing calls that fail with
+        /// `PhotoDna_ErrorLibraryFailure` before behaving as configured.
+        pub transient_failures: AtomicU32,
+        /// Names of the hashing entry points called, in order. Clone the
+        /// `Arc` before handing the mock to a generator to inspect it later.
+        pub calls: Arc<Mutex<Vec<&'static str>>>,
+        /// Options passed to each hashing call, in order.
+        pub options: Arc<Mutex<Vec<PhotoDnaOptions>>>,
+        /// Regions passed to each sub-region call, in order.
+        pub regions: Arc<Mutex<Vec<Region>>>,
+        /// Barrier `edge_hash` waits on when it starts and again before it
+        /// returns, so tests can observe calls in progress.
+        pub hash_barrier: Option<Arc<Barrier>>,
+    }
+
+    impl Default for MockBackend {
+        fn default() -> Self {
+            Self {
+                version: (1, 5, 1),
+                hash_return: 0,
+                error_number: 0,
+                fill_byte: 0xAB,
+                echo_input: false,
+                panic_on_hash: false,
+                transient_failures: AtomicU32::new(0),
+                calls: Arc::default(),
+                options: Arc::default(),
+                regions: Arc::default(),
+                hash_barrier: None,
+            }
+        }
+    }
+
+    impl Backend for MockBackend {
+        fn raw_instance(&self) -> *mut c_void {
+            std::ptr::null_mut()
+        }
+
+        fn library_path(&self) -> Option<&Path> {
+            None
+        }
+
+        fn error_number(&self) -> i32 {
+            self.error_number
+        }
+
+        fn error_string(&self, code: i32) -> Option<&str> {
+            Some(sys::error_code_description(code))
+        }
+
+        fn library_version(&self) -> i32 {
+            (self.version.0 << 16) | self.version.1
+        }
+
+        fn library_version_major(&self) -> i32 {
+            self.version.0
+        }
+
+        fn library_version_minor(&self) -> i32 {
+            self.version.1
+        }
+
+        fn library_version_patch(&self) -> i32 {
+            self.version.2
+        }
+
+        fn library_version_text(&self) -> Option<&str> {
+            Some("mock")
+        }
+
+        unsafe fn edge_hash(
+            &self,
+            image_data: *const u8,
+            hash_value: *mut u8,
+            _width: i32,
+            _height: i32,
+            _stride: i32,
+            options: PhotoDnaOptions,
+        ) -> i32 {
+            self.calls.lock().unwrap().push("edge_hash");
+            self.options.lock().unwrap().push(options);
+            assert!(!self.panic_on_hash, "mock library failure");
+            #[cfg(unix)]
+            if options & sys::PhotoDna_Verbose != 0 {
+                use std::io::Write;
+                use std::os::unix::io::FromRawFd;
+                // Write to descriptor 2 directly, as the library does,
+                // bypassing the test harness's output capture.
+                // SAFETY: Descriptor 2 is open; `ManuallyDrop` keeps it so.
+                let mut stderr =
+                    std::mem::ManuallyDrop::new(unsafe { std::fs::File::from_raw_fd(2) });
+                let _ = writeln!(stderr, "mock: edge_hash");
+            }
+            if let Some(barrier) = &self.hash_barrier {
+                barrier.wait();
+                barrier.wait();
+            }
+            let failing = self.transient_failures.fetch_update(
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+                |left| left.checked_sub(1),
+            );
+            if failing.is_ok() {
+                return sys::PhotoDna_ErrorLibraryFailure;
+            }
+            if self.hash_return >= 0 {
+                // SAFETY: Caller guarantees `hash_value` is writable for a
+                // full hash; tests using `echo_input` pass images of at least
+                // `HASH_SIZE` bytes.
+                unsafe {
+                    if self.echo_input {
+                        std::ptr::copy_nonoverlapping(image_data, hash_value, HASH_SIZE);
+                    } else {
+                        std::ptr::write_bytes(hash_value, self.fill_byte, HASH_SIZE);
+                    }
+                }
+            }
+            self.hash_return
+        }
+
+        unsafe fn edge_hash_border(
+            &self,
+            _image_data: *const u8,
+            hash_results: *mut HashResult,
+            max_hash_count: i32,
+            _width: i32,
+            _height: i32,
+            _stride: i32,
+            options: PhotoDnaOptions,
+        ) -> i32 {
+            self.calls.lock().unwrap().push("edge_hash_border");
+            self.options.lock().unwrap().push(options);
+            assert!(!self.panic_on_hash, "mock library failure");
+            if self.hash_return < 0 {
+                return self.hash_return;
+            }
+            if max_hash_count >= 1 {
+                // SAFETY: Caller guarantees at least `max_hash_count` entries.
+                let first = unsafe { &mut *hash_results };
+                first.result = 1;
+                first.hash[..HASH_SIZE].fill(self.fill_byte);
+            }
+            1
+        }
+
+        unsafe fn edge_hash_border_sub(
+            &self,
+            image_data: *const u8,
+            hash_results: *mut HashResult,
+            max_hash_count: i32,
+            width: i32,
+            height: i32,
+            stride: i32,
+            _x: i32,
+            _y: i32,
+            _w: i32,
+            _h: i32,
+            options: PhotoDnaOptions,
+        ) -> i32 {
+            self.calls.lock().unwrap().push("edge_hash_border_sub");
+            // SAFETY: Forwarded with the caller's guarantees.
+            unsafe {
+                self.edge_hash_border(
+                    image_data,
+                    hash_results,
+                    max_hash_count,
+                    width,
+                    height,
+                    stride,
+                    options,
+                )
+            }
+        }
+
+        unsafe fn edge_hash_sub(
+            &self,
+            image_data: *const u8,
+            hash_value: *mut u8,
+            width: i32,
+            height: i32,
+            stride: i32,
+            x: i32,
+            y: i32,
+            w: i32,
+            h: i32,
+            options: PhotoDnaOptions,
+        ) -> i32 {
+            self.calls.lock().unwrap().push("edge_hash_sub");
+            self.regions
+                .lock()
+                .unwrap()
+                .push(Region::from_xywh(x as u32, y as u32, w as u32, h as u32));
+            // SAFETY: Forwarded with the caller's guarantees.
+            unsafe { self.edge_hash(image_data, hash_value, width, height, stride, options) }
+        }
+    }
+}
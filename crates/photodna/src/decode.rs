@@ -0,0 +1,442 @@
+//! Hashing decoded images and image files (requires the `image` feature).
+//!
+//! These methods bridge the [`image`] crate and [`Generator`], choosing the
+//! pixel format from the decoded image so callers don't have to.
+
+use crate::{Generator, Hash, HashOptions, PhotoDnaError, PixelFormat, Result};
+use image::DynamicImage;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+impl Generator {
+    /// Computes a PhotoDNA hash from a decoded image.
+    ///
+    /// 8-bit grayscale and RGBA images are hashed in place. 16-bit
+    /// grayscale, RGB and RGBA images, common in microscopy and medical
+    /// imaging, are downconverted to 8 bits per channel by keeping the high
+    /// byte of each sample (`>> 8`); the low byte is discarded, so images
+    /// differing only in their finest shades hash the same. Every other
+    /// layout is converted to 8-bit RGB first. The pixel format in
+    /// `options` is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from [`compute_hash`](Self::compute_hash).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let image = image::open("photo.jpg")?;
+    /// let hash = generator.compute_hash_from_image(&image, HashOptions::default())?;
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "image")))]
+    pub fn compute_hash_from_image(
+        &self,
+        image: &DynamicImage,
+        options: HashOptions,
+    ) -> Result<Hash> {
+        let (width, height) = (image.width(), image.height());
+        self.check_pixels(width, height)?;
+
+        match image {
+            DynamicImage::ImageLuma8(gray) => self.compute_hash(
+                gray.as_raw(),
+                width,
+                height,
+                options.pixel_format(PixelFormat::Gray8),
+            ),
+            DynamicImage::ImageRgba8(rgba) => self.compute_hash(
+                rgba.as_raw(),
+                width,
+                height,
+                options.pixel_format(PixelFormat::Rgba),
+            ),
+            DynamicImage::ImageRgb8(rgb) => self.compute_hash(
+                rgb.as_raw(),
+                width,
+                height,
+                options.pixel_format(PixelFormat::Rgb),
+            ),
+            DynamicImage::ImageLuma16(gray) => {
+                self.compute_hash_16bit(gray.as_raw(), width, height, PixelFormat::Gray8, options)
+            }
+            DynamicImage::ImageRgb16(rgb) => {
+                self.compute_hash_16bit(rgb.as_raw(), width, height, PixelFormat::Rgb, options)
+            }
+            DynamicImage::ImageRgba16(rgba) => {
+                self.compute_hash_16bit(rgba.as_raw(), width, height, PixelFormat::Rgba, options)
+            }
+            other => self.compute_hash(
+                other.to_rgb8().as_raw(),
+                width,
+                height,
+                options.pixel_format(PixelFormat::Rgb),
+            ),
+        }
+    }
+
+    /// Hashes 16-bit samples after keeping the high byte of each in a
+    /// scratch buffer.
+    fn compute_hash_16bit(
+        &self,
+        samples: &[u16],
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        options: HashOptions,
+    ) -> Result<Hash> {
+        let mut scratch = self.scratch.take();
+        scratch.clear();
+        scratch.extend(samples.iter().map(|&sample| (sample >> 8) as u8));
+        self.compute_hash(&scratch, width, height, options.pixel_format(format))
+    }
+
+    /// Opens and decodes an image file, then computes its PhotoDNA hash.
+    ///
+    /// The file format is guessed from the contents. PNG and JPEG decoders
+    /// are enabled. With [`HashOptions::apply_exif_orientation`] set, the
+    /// decoded image is turned upright according to the file's EXIF
+    /// orientation tag first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PhotoDnaError::ImageDecode`] if the file cannot be read or
+    /// decoded, [`PhotoDnaError::ImageTooLarge`] if its header claims more
+    /// pixels than the generator accepts, or any error from
+    /// [`compute_hash_from_image`](Self::compute_hash_from_image).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let hash = generator.compute_hash_from_path("photo.jpg", HashOptions::default())?;
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "image")))]
+    pub fn compute_hash_from_path(
+        &self,
+        path: impl AsRef<Path>,
+        options: HashOptions,
+    ) -> Result<Hash> {
+        let path = path.as_ref();
+        let decode_error = |e: &dyn std::fmt::Display| {
+            PhotoDnaError::ImageDecode(format!("{}: {}", path.display(), e))
+        };
+        let bytes = std::fs::read(path).map_err(|e| decode_error(&e))?;
+        let reader = || {
+            image::io::Reader::new(Cursor::new(&bytes))
+                .with_guessed_format()
+                .map_err(|e| decode_error(&e))
+        };
+
+        // Check the header's claimed size before decoding allocates for it.
+        let (width, height) = reader()?.into_dimensions().map_err(|e| decode_error(&e))?;
+        self.check_pixels(width, height)?;
+        let mut image = reader()?.decode().map_err(|e| decode_error(&e))?;
+
+        if options.apply_exif_orientation {
+            if let Some(orientation) = exif_orientation(&bytes) {
+                image = orient(image, orientation);
+            }
+        }
+
+        self.compute_hash_from_image(&image, options)
+    }
+
+    /// Lazily hashes a sequence of image files.
+    ///
+    /// Each file is opened, decoded and hashed with
+    /// [`compute_hash_from_path`](Self::compute_hash_from_path) only when
+    /// the iterator reaches it, so at most one decoded image is held at a
+    /// time. A file that fails to decode or hash yields its error alongside
+    /// its path and iteration continues with the next one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let paths = [Path::new("a.jpg"), Path::new("b.png")];
+    /// for (path, result) in generator.hash_paths(paths, HashOptions::default()) {
+    ///     match result {
+    ///         Ok(hash) => println!("{}: {hash}", path.display()),
+    ///         Err(e) => eprintln!("skipping {}: {e}", path.display()),
+    ///     }
+    /// }
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "image")))]
+    pub fn hash_paths<'a, I>(
+        &'a self,
+        paths: I,
+        options: HashOptions,
+    ) -> impl Iterator<Item = (PathBuf, Result<Hash>)> + 'a
+    where
+        I: IntoIterator<Item = &'a Path>,
+        I::IntoIter: 'a,
+    {
+        paths.into_iter().map(move |path| {
+            (
+                path.to_path_buf(),
+                self.compute_hash_from_path(path, options),
+            )
+        })
+    }
+}
+
+/// Turns a decoded image upright according to an EXIF orientation value.
+fn orient(image: DynamicImage, orientation: u16) -> DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        7 => image.rotate270().fliph(),
+        8 => image.rotate270(),
+        _ => image,
+    }
+}
+
+/// Reads the EXIF orientation tag from a JPEG or PNG file, if present.
+fn exif_orientation(file: &[u8]) -> Option<u16> {
+    let tiff = if file.starts_with(&[0xFF, 0xD8]) {
+        jpeg_exif(file)?
+    } else if file.starts_with(b"\x89PNG\r\n\x1a\n") {
+        png_exif(file)?
+    } else {
+        return None;
+    };
+    tiff_orientation(tiff)
+}
+
+/// Returns the TIFF data of a JPEG's `Exif` APP1 segment.
+fn jpeg_exif(file: &[u8]) -> Option<&[u8]> {
+    let mut rest = &file[2..];
+    // Metadata segments all precede the start of scan (0xDA).
+    while rest.len() >= 4 && rest[0] == 0xFF && rest[1] != 0xDA {
+        let len = usize::from(u16::from_be_bytes([rest[2], rest[3]]));
+        let payload = rest.get(4..2 + len)?;
+        if rest[1] == 0xE1 {
+            if let Some(tiff) = payload.strip_prefix(b"Exif\0\0") {
+                return Some(tiff);
+            }
+        }
+        rest = &rest[2 + len..];
+    }
+    None
+}
+
+/// Returns the TIFF data of a PNG's `eXIf` chunk.
+fn png_exif(file: &[u8]) -> Option<&[u8]> {
+    let mut rest = &file[8..];
+    while rest.len() >= 12 {
+        let len = u32::from_be_bytes(rest[..4].try_into().ok()?) as usize;
+        let data = rest.get(8..8 + len)?;
+        if &rest[4..8] == b"eXIf" {
+            return Some(data);
+        }
+        rest = rest.get(12 + len..)?;
+    }
+    None
+}
+
+/// Reads the orientation tag (0x0112) from the first IFD of TIFF data.
+fn tiff_orientation(tiff: &[u8]) -> Option<u16> {
+    let big_endian = match tiff.get(..2)? {
+        b"II" => false,
+        b"MM" => true,
+        _ => return None,
+    };
+    let u16_at = |at: usize| {
+        let bytes = [*tiff.get(at)?, *tiff.get(at + 1)?];
+        Some(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let u32_at = |at: usize| {
+        let bytes: [u8; 4] = tiff.get(at..at + 4)?.try_into().ok()?;
+        Some(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+
+    let ifd = u32_at(4)? as usize;
+    for entry in 0..usize::from(u16_at(ifd)?) {
+        let at = ifd + 2 + entry * 12;
+        // A single SHORT is stored in the first two bytes of the value.
+        if u16_at(at)? == 0x0112 && u16_at(at + 2)? == 3 {
+            return u16_at(at + 8).filter(|value| (1..=8).contains(value));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::mock::MockBackend;
+    use image::{ImageBuffer, Rgb};
+
+    fn gradient(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(ImageBuffer::from_fn(width, height, |x, y| {
+            Rgb([x as u8, y as u8, (x + y) as u8])
+        }))
+    }
+
+    #[test]
+    fn test_compute_hash_from_image_converts_layouts() {
+        let generator = Generator::from_backend(MockBackend::default());
+        let image = gradient(64, 64);
+
+        for image in [
+            image.clone(),
+            DynamicImage::ImageLuma8(image.to_luma8()),
+            DynamicImage::ImageRgba8(image.to_rgba8()),
+            DynamicImage::ImageLumaA16(image.to_luma_alpha16()),
+        ] {
+            let hash = generator
+                .compute_hash_from_image(&image, HashOptions::new())
+                .unwrap();
+            assert_eq!(hash.as_bytes()[0], 0xAB);
+        }
+    }
+
+    #[test]
+    fn test_compute_hash_from_image_downconverts_16bit() {
+        let backend = MockBackend {
+            echo_input: true,
+            ..Default::default()
+        };
+        let calls = backend.options.clone();
+        let generator = Generator::from_backend(backend);
+
+        // Low bytes of 0xFF would round up in a conversion to 8 bits, but
+        // are dropped by the downconversion.
+        let image = DynamicImage::ImageRgb16(ImageBuffer::from_fn(64, 64, |x, y| {
+            let sample = |v: u32| ((v as u16) << 8) | 0xFF;
+            Rgb([sample(x), sample(y), sample(x + y)])
+        }));
+        let hash = generator
+            .compute_hash_from_image(&image, HashOptions::new())
+            .unwrap();
+        assert_eq!(&hash.as_bytes()[..6], &[0, 0, 0, 1, 0, 1]);
+        assert_eq!(
+            calls.lock().unwrap()[0] & photodna_sys::PhotoDna_PixelLayoutMask,
+            photodna_sys::PhotoDna_Rgb
+        );
+
+        let gray = DynamicImage::ImageLuma16(ImageBuffer::from_fn(64, 64, |x, _| {
+            image::Luma([((x as u16) << 8) | 0xFF])
+        }));
+        let hash = generator
+            .compute_hash_from_image(&gray, HashOptions::new())
+            .unwrap();
+        assert_eq!(&hash.as_bytes()[..3], &[0, 1, 2]);
+        assert_eq!(
+            calls.lock().unwrap()[1] & photodna_sys::PhotoDna_PixelLayoutMask,
+            photodna_sys::PhotoDna_Grey8
+        );
+    }
+
+    #[test]
+    fn test_compute_hash_from_path() {
+        let generator = Generator::from_backend(MockBackend::default());
+        let path = std::env::temp_dir().join(format!("photodna-decode-{}.png", std::process::id()));
+        gradient(64, 64).save(&path).unwrap();
+
+        let result = generator.compute_hash_from_path(&path, HashOptions::new());
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_ok());
+
+        let missing = generator.compute_hash_from_path(&path, HashOptions::new());
+        assert!(matches!(missing, Err(PhotoDnaError::ImageDecode(_))));
+    }
+
+    /// Encodes `image` as a JPEG carrying an EXIF orientation tag.
+    fn jpeg_with_orientation(image: &DynamicImage, orientation: u16) -> Vec<u8> {
+        let mut jpeg = Vec::new();
+        image
+            .write_to(
+                &mut Cursor::new(&mut jpeg),
+                image::ImageOutputFormat::Jpeg(95),
+            )
+            .unwrap();
+
+        // Big-endian TIFF header and a one-entry IFD holding the tag.
+        let mut exif = b"Exif\0\0MM\0\x2a\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01".to_vec();
+        exif.extend_from_slice(&orientation.to_be_bytes());
+        exif.extend_from_slice(&[0; 6]);
+        let mut segment = vec![0xFF, 0xE1];
+        segment.extend_from_slice(&(exif.len() as u16 + 2).to_be_bytes());
+        segment.extend_from_slice(&exif);
+
+        jpeg.splice(2..2, segment);
+        jpeg
+    }
+
+    #[test]
+    fn test_apply_exif_orientation() {
+        let generator = Generator::from_backend(MockBackend {
+            echo_input: true,
+            ..Default::default()
+        });
+        // Black left half, white right half; stored as if the camera was
+        // turned, so upright it is black on top.
+        let image = DynamicImage::ImageLuma8(ImageBuffer::from_fn(80, 64, |x, _| {
+            image::Luma([if x < 40 { 0 } else { 255 }])
+        }));
+        let path = std::env::temp_dir().join(format!("photodna-exif-{}.jpg", std::process::id()));
+        std::fs::write(&path, jpeg_with_orientation(&image, 6)).unwrap();
+        assert_eq!(exif_orientation(&std::fs::read(&path).unwrap()), Some(6));
+
+        let options = HashOptions::new();
+        let as_stored = generator.compute_hash_from_path(&path, options);
+        let upright = generator.compute_hash_from_path(&path, options.apply_exif_orientation(true));
+        std::fs::remove_file(&path).unwrap();
+
+        // The first rows of the stored image cross both halves; upright,
+        // the first rows are all black.
+        assert!(as_stored.unwrap().as_bytes().iter().any(|&b| b > 200));
+        assert!(upright.unwrap().as_bytes().iter().all(|&b| b < 50));
+    }
+
+    #[test]
+    fn test_orient_matches_exif_transforms() {
+        let image = gradient(3, 2);
+        let pixel = |image: &DynamicImage, x, y| image.to_rgb8().get_pixel(x, y).0;
+        // Orientation 5 stores the transpose; 7 the transverse.
+        let transposed = orient(image.clone(), 5);
+        assert_eq!((transposed.width(), transposed.height()), (2, 3));
+        assert_eq!(pixel(&transposed, 1, 2), pixel(&image, 2, 1));
+        let transversed = orient(image.clone(), 7);
+        assert_eq!(pixel(&transversed, 0, 0), pixel(&image, 2, 1));
+        assert_eq!(orient(image.clone(), 1), image);
+    }
+
+    #[test]
+    fn test_hash_paths_isolates_errors() {
+        let generator = Generator::from_backend(MockBackend::default());
+        let dir = std::env::temp_dir();
+        let id = std::process::id();
+        let first = dir.join(format!("photodna-paths-{id}-a.png"));
+        let text = dir.join(format!("photodna-paths-{id}-b.txt"));
+        let second = dir.join(format!("photodna-paths-{id}-c.png"));
+        gradient(64, 64).save(&first).unwrap();
+        std::fs::write(&text, "not an image").unwrap();
+        gradient(80, 64).save(&second).unwrap();
+
+        let paths = [first.as_path(), text.as_path(), second.as_path()];
+        let results: Vec<_> = generator.hash_paths(paths, HashOptions::new()).collect();
+        for path in paths {
+            std::fs::remove_file(path).unwrap();
+        }
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].0, first);
+        assert!(results[0].1.is_ok());
+        assert_eq!(results[1].0, text);
+        assert!(matches!(results[1].1, Err(PhotoDnaError::ImageDecode(_))));
+        assert_eq!(results[2].0, second);
+        assert!(results[2].1.is_ok());
+    }
+}
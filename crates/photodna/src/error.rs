@@ -5,6 +5,8 @@
 // Allow non-standard constant names from photodna-sys (C-style naming)
 #![allow(non_upper_case_globals)]
 
+use std::fmt;
+use std::path::PathBuf;
 use thiserror::Error;
 
 /// Result type alias for PhotoDNA operations.
@@ -17,8 +19,17 @@ pub type Result<T> = std::result::Result<T, PhotoDnaError>;
 #[derive(Error, Debug, Clone, PartialEq, Eq)]
 pub enum PhotoDnaError {
     /// Failed to load or initialize the PhotoDNA library.
+    ///
+    /// The report lists every library path that was tried and why it failed.
     #[error("failed to initialize PhotoDNA library: {0}")]
-    InitializationFailed(String),
+    InitializationFailed(LibraryLoadReport),
+
+    /// No PhotoDNA backend exists for the target platform.
+    ///
+    /// Returned by [`Generator::new`](crate::Generator::new) on platforms
+    /// other than Windows, Linux and macOS.
+    #[error("PhotoDNA is not supported on this platform")]
+    UnsupportedPlatform,
 
     /// An undetermined error occurred within the library.
     #[error("an undetermined error occurred (error code: -7000)")]
@@ -57,6 +68,11 @@ pub enum PhotoDnaError {
     BadArgument,
 
     /// The image has few or no gradients.
+    ///
+    /// The library resizes images internally before hashing, so very
+    /// elongated images (such as 51×10000) can lose their gradients even
+    /// when the full-size image has plenty. The library does not report
+    /// the size it works at.
     #[error("image has few or no gradients (image is flat)")]
     ImageIsFlat,
 
@@ -85,20 +101,143 @@ pub enum PhotoDnaError {
         actual: usize,
     },
 
-    /// Invalid image dimensions (width or height is zero or negative).
+    /// Invalid image dimensions (width or height is zero or exceeds `i32::MAX`).
     #[error("invalid image dimensions: {width}x{height}")]
     InvalidDimensions {
         /// The width provided.
-        width: i32,
+        width: u32,
         /// The height provided.
-        height: i32,
+        height: u32,
     },
 
+    /// The image has more pixels than
+    /// [`GeneratorOptions::max_pixels`](crate::GeneratorOptions::max_pixels)
+    /// allows.
+    #[error("image too large: {width}x{height} exceeds the limit of {max_pixels} pixels")]
+    ImageTooLarge {
+        /// The width provided.
+        width: u32,
+        /// The height provided.
+        height: u32,
+        /// The generator's pixel limit.
+        max_pixels: u64,
+    },
+
+    /// A [`RowHasher`](crate::RowHasher) received more or fewer rows than
+    /// the image height.
+    #[error("expected {expected} image rows, got {actual}")]
+    RowCount {
+        /// The image height.
+        expected: u32,
+        /// The number of rows supplied.
+        actual: u32,
+    },
+
+    /// No supported pixel format has this many channels.
+    #[error("unsupported pixel format: {channels} channels per pixel")]
+    UnsupportedPixelFormat {
+        /// The channel count provided.
+        channels: u8,
+    },
+
+    /// An image file could not be read or decoded (`image` feature).
+    #[error("failed to decode image: {0}")]
+    ImageDecode(String),
+
+    /// An array's shape does not match the pixel format (`ndarray` feature).
+    #[error("array does not match pixel format: {0}")]
+    ArrayLayout(String),
+
+    /// Standard error could not be redirected to capture the library's
+    /// verbose output (`capture-verbose` feature).
+    #[error("failed to capture verbose output: {0}")]
+    VerboseCapture(String),
+
+    /// The library failed the start-up self-check of
+    /// [`Generator::new_verified`](crate::Generator::new_verified).
+    #[error("library verification failed: {0}")]
+    VerificationFailed(String),
+
     /// An unknown error code was returned by the library.
     #[error("unknown error code: {0}")]
     UnknownErrorCode(i32),
 }
 
+/// A coarse grouping of [`PhotoDnaError`] variants.
+///
+/// Returned by [`PhotoDnaError::category`]. The set is small and stable
+/// enough to use as a metrics label, e.g.
+/// `photodna_errors_total{category="input"}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// The caller's image or arguments were rejected.
+    Input,
+    /// Memory or another system resource was unavailable.
+    Resource,
+    /// A hash was malformed.
+    Format,
+    /// The library failed for an undetermined reason.
+    Internal,
+    /// The library could not be loaded, initialized or verified.
+    Setup,
+}
+
+impl ErrorCategory {
+    /// Returns the lowercase name of this category, e.g. `"input"`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Input => "input",
+            Self::Resource => "resource",
+            Self::Format => "format",
+            Self::Internal => "internal",
+            Self::Setup => "setup",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Why the PhotoDNA library could not be loaded or initialized.
+///
+/// Produced by [`Generator::new`](crate::Generator::new) and carried by
+/// [`PhotoDnaError::InitializationFailed`]. Displays as a multi-line
+/// diagnostic with one line per path tried.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LibraryLoadReport {
+    /// Summary of the failure.
+    pub reason: String,
+    /// Each library path that failed to load, with the loader's error message.
+    pub attempts: Vec<(PathBuf, String)>,
+}
+
+impl fmt::Display for LibraryLoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.reason)?;
+        for (path, error) in &self.attempts {
+            write!(f, "\n  tried '{}': {}", path.display(), error)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+impl From<photodna_sys::LibraryLoadReport> for LibraryLoadReport {
+    fn from(report: photodna_sys::LibraryLoadReport) -> Self {
+        Self {
+            reason: report.reason().to_string(),
+            attempts: report
+                .attempts()
+                .iter()
+                .map(|(path, error)| (path.clone(), error.to_string()))
+                .collect(),
+        }
+    }
+}
+
 impl PhotoDnaError {
     /// Creates an error from a PhotoDNA library error code.
     ///
@@ -157,8 +296,60 @@ impl PhotoDnaError {
             Self::InvalidSubImage => Some(PhotoDna_ErrorInvalidSubImage),
             Self::UnknownErrorCode(code) => Some(*code),
             Self::InitializationFailed(_)
+            | Self::UnsupportedPlatform
             | Self::BufferTooSmall { .. }
-            | Self::InvalidDimensions { .. } => None,
+            | Self::InvalidDimensions { .. }
+            | Self::ImageTooLarge { .. }
+            | Self::UnsupportedPixelFormat { .. }
+            | Self::RowCount { .. }
+            | Self::ImageDecode(_)
+            | Self::ArrayLayout(_)
+            | Self::VerboseCapture(_)
+            | Self::VerificationFailed(_) => None,
+        }
+    }
+
+    /// Returns the category this error belongs to.
+    ///
+    /// Every [input error](Self::is_input_error) is
+    /// [`ErrorCategory::Input`], as is [`NoBorder`](Self::NoBorder), which
+    /// depends only on the image.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::{ErrorCategory, PhotoDnaError};
+    ///
+    /// assert_eq!(PhotoDnaError::ImageTooSmall.category(), ErrorCategory::Input);
+    /// assert_eq!(PhotoDnaError::ImageTooSmall.category().name(), "input");
+    /// ```
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::ImageTooSmall
+            | Self::ImageIsFlat
+            | Self::NoBorder
+            | Self::NoBorderImageTooSmall
+            | Self::BadArgument
+            | Self::InvalidStride
+            | Self::InvalidSubImage
+            | Self::SourceFormatUnknown
+            | Self::BufferTooSmall { .. }
+            | Self::InvalidDimensions { .. }
+            | Self::ImageTooLarge { .. }
+            | Self::UnsupportedPixelFormat { .. }
+            | Self::RowCount { .. }
+            | Self::ImageDecode(_)
+            | Self::ArrayLayout(_) => ErrorCategory::Input,
+            Self::MemoryAllocationFailed | Self::MemoryAccess | Self::VerboseCapture(_) => {
+                ErrorCategory::Resource
+            }
+            Self::InvalidHash | Self::HashFormatInvalidCharacters => ErrorCategory::Format,
+            Self::Unknown | Self::LibraryFailure | Self::UnknownErrorCode(_) => {
+                ErrorCategory::Internal
+            }
+            Self::InitializationFailed(_)
+            | Self::UnsupportedPlatform
+            | Self::VerificationFailed(_) => ErrorCategory::Setup,
         }
     }
 
@@ -186,11 +377,35 @@ impl PhotoDnaError {
                 | Self::SourceFormatUnknown
                 | Self::BufferTooSmall { .. }
                 | Self::InvalidDimensions { .. }
+                | Self::ImageTooLarge { .. }
                 | Self::NoBorderImageTooSmall
+                | Self::UnsupportedPixelFormat { .. }
+                | Self::RowCount { .. }
+                | Self::ImageDecode(_)
+                | Self::ArrayLayout(_)
         )
     }
 }
 
+impl From<PhotoDnaError> for std::io::Error {
+    /// Maps [input errors](PhotoDnaError::is_input_error) to
+    /// [`InvalidInput`](std::io::ErrorKind::InvalidInput) and everything
+    /// else to [`Other`](std::io::ErrorKind::Other).
+    ///
+    /// The original error is kept as the I/O error's inner error, so the
+    /// message is unchanged and it can be recovered with
+    /// [`get_ref`](std::io::Error::get_ref) or
+    /// [`into_inner`](std::io::Error::into_inner).
+    fn from(error: PhotoDnaError) -> Self {
+        let kind = if error.is_input_error() {
+            std::io::ErrorKind::InvalidInput
+        } else {
+            std::io::ErrorKind::Other
+        };
+        Self::new(kind, error)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -232,4 +447,127 @@ mod tests {
         assert!(PhotoDnaError::InvalidStride.is_input_error());
         assert!(!PhotoDnaError::MemoryAllocationFailed.is_input_error());
     }
+
+    #[test]
+    fn test_category() {
+        let cases = [
+            (PhotoDnaError::ImageTooSmall, ErrorCategory::Input),
+            (PhotoDnaError::ImageIsFlat, ErrorCategory::Input),
+            (PhotoDnaError::NoBorder, ErrorCategory::Input),
+            (PhotoDnaError::NoBorderImageTooSmall, ErrorCategory::Input),
+            (PhotoDnaError::BadArgument, ErrorCategory::Input),
+            (PhotoDnaError::InvalidStride, ErrorCategory::Input),
+            (PhotoDnaError::InvalidSubImage, ErrorCategory::Input),
+            (PhotoDnaError::SourceFormatUnknown, ErrorCategory::Input),
+            (
+                PhotoDnaError::BufferTooSmall {
+                    expected: 2,
+                    actual: 1,
+                },
+                ErrorCategory::Input,
+            ),
+            (
+                PhotoDnaError::InvalidDimensions {
+                    width: 0,
+                    height: 0,
+                },
+                ErrorCategory::Input,
+            ),
+            (
+                PhotoDnaError::ImageTooLarge {
+                    width: 2,
+                    height: 2,
+                    max_pixels: 3,
+                },
+                ErrorCategory::Input,
+            ),
+            (
+                PhotoDnaError::RowCount {
+                    expected: 2,
+                    actual: 1,
+                },
+                ErrorCategory::Input,
+            ),
+            (
+                PhotoDnaError::UnsupportedPixelFormat { channels: 2 },
+                ErrorCategory::Input,
+            ),
+            (
+                PhotoDnaError::ImageDecode(String::new()),
+                ErrorCategory::Input,
+            ),
+            (
+                PhotoDnaError::ArrayLayout(String::new()),
+                ErrorCategory::Input,
+            ),
+            (
+                PhotoDnaError::MemoryAllocationFailed,
+                ErrorCategory::Resource,
+            ),
+            (PhotoDnaError::MemoryAccess, ErrorCategory::Resource),
+            (
+                PhotoDnaError::VerboseCapture(String::new()),
+                ErrorCategory::Resource,
+            ),
+            (PhotoDnaError::InvalidHash, ErrorCategory::Format),
+            (
+                PhotoDnaError::HashFormatInvalidCharacters,
+                ErrorCategory::Format,
+            ),
+            (PhotoDnaError::Unknown, ErrorCategory::Internal),
+            (PhotoDnaError::LibraryFailure, ErrorCategory::Internal),
+            (
+                PhotoDnaError::UnknownErrorCode(-9999),
+                ErrorCategory::Internal,
+            ),
+            (
+                PhotoDnaError::InitializationFailed(LibraryLoadReport::default()),
+                ErrorCategory::Setup,
+            ),
+            (PhotoDnaError::UnsupportedPlatform, ErrorCategory::Setup),
+            (
+                PhotoDnaError::VerificationFailed(String::new()),
+                ErrorCategory::Setup,
+            ),
+        ];
+
+        for (error, category) in cases {
+            assert_eq!(error.category(), category, "{error:?}");
+            if error.is_input_error() {
+                assert_eq!(category, ErrorCategory::Input);
+            }
+        }
+        assert_eq!(ErrorCategory::Resource.to_string(), "resource");
+    }
+
+    #[test]
+    fn test_into_io_error() {
+        let io = std::io::Error::from(PhotoDnaError::ImageTooSmall);
+        assert_eq!(io.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(io.to_string(), PhotoDnaError::ImageTooSmall.to_string());
+        assert_eq!(
+            io.get_ref().unwrap().downcast_ref::<PhotoDnaError>(),
+            Some(&PhotoDnaError::ImageTooSmall)
+        );
+
+        let io = std::io::Error::from(PhotoDnaError::LibraryFailure);
+        assert_eq!(io.kind(), std::io::ErrorKind::Other);
+    }
+
+    #[test]
+    fn test_library_load_report_display() {
+        let error = PhotoDnaError::InitializationFailed(LibraryLoadReport {
+            reason: "Failed to load library".to_string(),
+            attempts: vec![
+                (PathBuf::from("/a/lib.so"), "not found".to_string()),
+                (PathBuf::from("/b/lib.so"), "bad ELF".to_string()),
+            ],
+        });
+
+        let message = error.to_string();
+        assert_eq!(message.lines().count(), 3);
+        assert!(message.contains("tried '/a/lib.so': not found"));
+        assert!(message.contains("tried '/b/lib.so': bad ELF"));
+        assert_eq!(error.error_code(), None);
+    }
 }
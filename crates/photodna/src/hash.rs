@@ -3,7 +3,9 @@
 //! This module provides the [`Hash`] type, a fixed-size container for
 //! PhotoDNA perceptual hashes with zero-copy semantics.
 
+use crate::PhotoDnaError;
 use std::fmt;
+use std::io::{self, Read, Write};
 
 /// Size of PhotoDNA Edge V2 hash in bytes (binary format).
 ///
@@ -12,9 +14,26 @@ pub const HASH_SIZE: usize = photodna_sys::PHOTODNA_HASH_SIZE_EDGE_V2;
 
 /// Maximum possible hash buffer size.
 ///
-/// Use this when you need to support any hash format, including Base64.
+/// This is the length of an Edge V2 hash in the library's Base64 output
+/// format. [`Hash`](struct@Hash) always stores the decoded binary form; use
+/// [`Hash::from_slice_max`] to accept buffers of either size.
 pub const HASH_SIZE_MAX: usize = photodna_sys::PHOTODNA_HASH_SIZE_MAX;
 
+/// A commonly used distance threshold for matching Edge V2 hashes.
+///
+/// This is a starting point, not a guarantee: the right threshold depends
+/// on the corpus and the cost of false positives. See
+/// [`matching::distance_histogram`](crate::matching::distance_histogram)
+/// for calibrating one from data.
+pub const DEFAULT_MATCH_THRESHOLD: f64 = 1800.0;
+
+/// The largest possible distance between two full hashes.
+///
+/// This is `sqrt(HASH_SIZE * 255²)`, reached when every byte of one hash
+/// is 0 and every byte of the other is 255. Dividing a distance by it
+/// gives a value from 0 to 1; [`Hash::similarity`] is one minus that.
+pub const MAX_DISTANCE: f64 = 7751.328918321038;
+
 /// A PhotoDNA perceptual hash.
 ///
 /// This type wraps a fixed-size byte array containing the raw hash bytes.
@@ -26,8 +45,15 @@ pub const HASH_SIZE_MAX: usize = photodna_sys::PHOTODNA_HASH_SIZE_MAX;
 ///
 /// # Size
 ///
-/// The hash is 924 bytes (Edge V2 binary format). For Base64-encoded hashes,
-/// use the raw byte methods and encode/decode as needed.
+/// The hash is 924 bytes (Edge V2 binary format). Base64-encoded hashes
+/// (1232 bytes) are decoded on the way in by [`from_base64`](Self::from_base64)
+/// or [`from_slice_max`](Self::from_slice_max) and encoded on the way out by
+/// [`to_base64`](Self::to_base64).
+///
+/// Always normalize to binary before comparing. A hash filled straight from
+/// Base64 text reports [`HashFormat::Base64Text`] from
+/// [`format`](Self::format), and distances against it are meaningless; the
+/// distance methods panic on mixed formats in debug builds.
 ///
 /// # Examples
 ///
@@ -109,6 +135,103 @@ impl Hash {
         })
     }
 
+    /// Creates a hash from a slice of exactly [`HASH_SIZE`] bytes.
+    ///
+    /// A strict counterpart to [`from_slice`](Self::from_slice) for
+    /// ingestion paths that must reject partial hashes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::{Hash, HASH_SIZE};
+    ///
+    /// assert!(Hash::from_exact(&[0xAB; HASH_SIZE]).is_some());
+    /// assert!(Hash::from_exact(&[0xAB; 100]).is_none());
+    /// ```
+    pub fn from_exact(slice: &[u8]) -> Option<Self> {
+        let bytes: [u8; HASH_SIZE] = slice.try_into().ok()?;
+        Some(Self::new(bytes))
+    }
+
+    /// Creates a hash from a buffer of up to [`HASH_SIZE_MAX`] bytes.
+    ///
+    /// Buffers of at most [`HASH_SIZE`] bytes are taken as binary hash data,
+    /// exactly like [`from_slice`](Self::from_slice). Longer buffers can only
+    /// be Base64 output from the library (which is [`HASH_SIZE_MAX`] bytes for
+    /// an Edge V2 hash) and are decoded to binary.
+    ///
+    /// # Errors
+    ///
+    /// - [`PhotoDnaError::InvalidHash`] if the buffer exceeds
+    ///   [`HASH_SIZE_MAX`] bytes, or decodes to more than [`HASH_SIZE`] bytes.
+    /// - [`PhotoDnaError::HashFormatInvalidCharacters`] if a buffer longer
+    ///   than [`HASH_SIZE`] is not valid Base64.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::{Hash, HASH_SIZE, HASH_SIZE_MAX};
+    ///
+    /// let binary = Hash::new([7; HASH_SIZE]);
+    /// let encoded = binary.to_base64();
+    /// assert_eq!(encoded.len(), HASH_SIZE_MAX);
+    ///
+    /// let decoded = Hash::from_slice_max(encoded.as_bytes()).unwrap();
+    /// assert_eq!(decoded, binary);
+    /// ```
+    pub fn from_slice_max(slice: &[u8]) -> crate::Result<Self> {
+        if slice.len() <= HASH_SIZE {
+            return Self::from_slice(slice).ok_or(PhotoDnaError::InvalidHash);
+        }
+        if slice.len() > HASH_SIZE_MAX {
+            return Err(PhotoDnaError::InvalidHash);
+        }
+
+        let text =
+            std::str::from_utf8(slice).map_err(|_| PhotoDnaError::HashFormatInvalidCharacters)?;
+        let text = text.trim_end_matches('\0');
+        if text.len() % 4 != 0 || base64_decoded_len(text) > HASH_SIZE {
+            return Err(PhotoDnaError::InvalidHash);
+        }
+        Self::from_base64(text).ok_or(PhotoDnaError::HashFormatInvalidCharacters)
+    }
+
+    /// Reports whether the hash holds binary data or undecoded Base64 text.
+    ///
+    /// A full hash whose every byte is a Base64 character is almost certainly
+    /// the library's Base64 output copied in as-is; real binary hashes never
+    /// look like that. Partial hashes are always reported as binary.
+    ///
+    /// This is a heuristic over all the bytes, meant to be run once where
+    /// hashes enter a system, as [`Generator::validate_hash`] does. The
+    /// distance methods only check it in debug builds.
+    ///
+    /// [`Generator::validate_hash`]: crate::Generator::validate_hash
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::{Hash, HashFormat, HASH_SIZE};
+    ///
+    /// let binary = Hash::new([7; HASH_SIZE]);
+    /// assert_eq!(binary.format(), HashFormat::Binary);
+    ///
+    /// let text = binary.to_base64();
+    /// let raw = Hash::from_slice(&text.as_bytes()[..HASH_SIZE]).unwrap();
+    /// assert_eq!(raw.format(), HashFormat::Base64Text);
+    /// ```
+    pub fn format(&self) -> HashFormat {
+        let looks_like_text = self
+            .as_bytes()
+            .iter()
+            .all(|&c| c == b'=' || base64_digit_value(c).is_some());
+        if self.is_full() && looks_like_text {
+            HashFormat::Base64Text
+        } else {
+            HashFormat::Binary
+        }
+    }
+
     /// Returns the hash bytes as a slice.
     ///
     /// The returned slice contains only the valid hash bytes (up to `len()`).
@@ -125,12 +248,52 @@ impl Hash {
         &self.bytes
     }
 
+    /// Copies the valid hash bytes into a new `Vec`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::Hash;
+    ///
+    /// let hash = Hash::from_slice(&[1, 2, 3]).unwrap();
+    /// assert_eq!(hash.to_vec(), vec![1, 2, 3]);
+    /// ```
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    /// Consumes the hash and returns its full buffer.
+    ///
+    /// Bytes past [`len`](Self::len) are zeroed, matching the record
+    /// written by [`write_to`](Self::write_to).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::{Hash, HASH_SIZE};
+    ///
+    /// let array = Hash::from_slice(&[1, 2]).unwrap().into_array();
+    /// assert_eq!(array.len(), HASH_SIZE);
+    /// assert_eq!(array[..3], [1, 2, 0]);
+    /// ```
+    pub fn into_array(mut self) -> [u8; HASH_SIZE] {
+        // `set_len` may leave stale bytes past `len`.
+        self.bytes[self.len..].fill(0);
+        self.bytes
+    }
+
     /// Returns the length of valid hash bytes.
     #[inline]
     pub const fn len(&self) -> usize {
         self.len
     }
 
+    /// Returns `true` if the hash holds a full [`HASH_SIZE`] bytes.
+    #[inline]
+    pub const fn is_full(&self) -> bool {
+        self.len == HASH_SIZE
+    }
+
     /// Returns `true` if all hash bytes are zero.
     ///
     /// An empty hash typically indicates that no hash was computed.
@@ -224,153 +387,1129 @@ impl Hash {
         })
     }
 
-    /// Returns a mutable slice to the entire hash buffer.
+    /// Returns `true` if `hex` encodes exactly this hash.
     ///
-    /// This is useful for passing to FFI functions that write directly
-    /// to the buffer.
+    /// `hex` is parsed as by [`from_hex`](Self::from_hex), so case is
+    /// ignored but the length must match: a prefix of the hash's hex does
+    /// not compare equal. Returns `false` if `hex` is not valid hex.
     ///
-    /// # Safety
+    /// # Examples
     ///
-    /// After modifying the buffer via this method, you may need to update
-    /// the logical length using [`set_len`](Self::set_len) if the actual
-    /// data size has changed.
-    #[inline]
-    pub fn as_mut_bytes(&mut self) -> &mut [u8; HASH_SIZE] {
-        &mut self.bytes
+    /// ```rust
+    /// use photodna::Hash;
+    ///
+    /// let hash = Hash::from_slice(&[0xAB, 0xCD]).unwrap();
+    /// assert!(hash.eq_hex("abcd"));
+    /// assert!(hash.eq_hex("ABCD"));
+    /// assert!(!hash.eq_hex("ab"));
+    /// assert!(!hash.eq_hex("not hex"));
+    /// ```
+    pub fn eq_hex(&self, hex: &str) -> bool {
+        Self::from_hex(hex).is_some_and(|parsed| parsed.as_bytes() == self.as_bytes())
     }
 
-    /// Sets the length of valid hash data.
+    /// Encodes the hash as standard, padded Base64.
     ///
-    /// # Panics
+    /// A full Edge V2 hash encodes to [`HASH_SIZE_MAX`] characters, matching
+    /// the library's Base64 output format.
     ///
-    /// Panics if `len > HASH_SIZE`.
-    #[inline]
-    pub fn set_len(&mut self, len: usize) {
-        assert!(len <= HASH_SIZE, "length exceeds maximum hash size");
-        self.len = len;
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::Hash;
+    ///
+    /// let hash = Hash::from_slice(b"PDNA").unwrap();
+    /// assert_eq!(hash.to_base64(), "UEROQQ==");
+    /// ```
+    pub fn to_base64(&self) -> String {
+        let bytes = self.as_bytes();
+        let mut out = String::with_capacity((bytes.len() + 2) / 3 * 4);
+
+        for chunk in bytes.chunks(3) {
+            let b = [
+                chunk[0],
+                chunk.get(1).copied().unwrap_or(0),
+                chunk.get(2).copied().unwrap_or(0),
+            ];
+            let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+
+            out.push(BASE64_ALPHABET[(n >> 18) as usize & 0x3F] as char);
+            out.push(BASE64_ALPHABET[(n >> 12) as usize & 0x3F] as char);
+            if chunk.len() > 1 {
+                out.push(BASE64_ALPHABET[(n >> 6) as usize & 0x3F] as char);
+            } else {
+                out.push('=');
+            }
+            if chunk.len() > 2 {
+                out.push(BASE64_ALPHABET[n as usize & 0x3F] as char);
+            } else {
+                out.push('=');
+            }
+        }
+        out
     }
 
-    /// Creates a new hash with uninitialized content.
+    /// Parses a hash from a standard, padded Base64 string.
     ///
-    /// This is useful for performance-critical code where the hash
-    /// will be immediately overwritten by FFI.
+    /// # Returns
     ///
-    /// # Safety
+    /// Returns `Some(Hash)` if decoding succeeds, `None` if the string
+    /// contains invalid characters, is not padded to a multiple of four
+    /// characters, or decodes to more than [`HASH_SIZE`] bytes.
     ///
-    /// The caller must ensure the hash is fully initialized before
-    /// reading from it.
-    #[inline]
-    pub const fn zeroed() -> Self {
-        Self {
-            bytes: [0u8; HASH_SIZE],
-            len: 0,
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::Hash;
+    ///
+    /// let hash = Hash::from_base64("UEROQQ==").unwrap();
+    /// assert_eq!(hash.as_bytes(), b"PDNA");
+    /// ```
+    pub fn from_base64(text: &str) -> Option<Self> {
+        let input = text.as_bytes();
+        if input.len() % 4 != 0 {
+            return None;
         }
-    }
-}
 
-impl Default for Hash {
-    fn default() -> Self {
-        Self {
-            bytes: [0u8; HASH_SIZE],
-            len: HASH_SIZE,
+        let byte_len = base64_decoded_len(text);
+        if byte_len > HASH_SIZE {
+            return None;
         }
-    }
-}
 
-impl fmt::Debug for Hash {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // Show first 16 bytes as hex for readability
-        let preview_len = 16.min(self.len);
-        let preview: String = self.bytes[..preview_len]
-            .iter()
-            .map(|b| format!("{:02x}", b))
-            .collect();
+        let mut bytes = [0u8; HASH_SIZE];
+        let mut written = 0;
+        let chunk_count = input.len() / 4;
 
-        if self.len > preview_len {
-            write!(f, "Hash({}..., {} bytes)", preview, self.len)
-        } else {
-            write!(f, "Hash({})", preview)
-        }
-    }
-}
+        for (i, chunk) in input.chunks(4).enumerate() {
+            // Padding is only allowed at the end of the final chunk
+            let padding = if i + 1 == chunk_count {
+                chunk.iter().rev().take_while(|&&c| c == b'=').count()
+            } else {
+                0
+            };
+            if padding > 2 {
+                return None;
+            }
 
-impl fmt::Display for Hash {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.to_hex())
-    }
-}
+            let mut n = 0u32;
+            for &c in &chunk[..4 - padding] {
+                n = (n << 6) | u32::from(base64_digit_value(c)?);
+            }
+            n <<= 6 * padding as u32;
 
-impl AsRef<[u8]> for Hash {
-    fn as_ref(&self) -> &[u8] {
-        self.as_bytes()
-    }
-}
+            let decoded = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+            let take = 3 - padding;
+            bytes[written..written + take].copy_from_slice(&decoded[..take]);
+            written += take;
+        }
 
-impl From<[u8; HASH_SIZE]> for Hash {
-    fn from(bytes: [u8; HASH_SIZE]) -> Self {
-        Self::new(bytes)
+        Some(Self {
+            bytes,
+            len: byte_len,
+        })
     }
-}
 
-impl TryFrom<&[u8]> for Hash {
-    type Error = ();
-
-    fn try_from(slice: &[u8]) -> Result<Self, Self::Error> {
-        Self::from_slice(slice).ok_or(())
+    /// Writes the hash as a fixed-width record of exactly [`HASH_SIZE`] bytes.
+    ///
+    /// Hashes shorter than [`HASH_SIZE`] are zero-padded, so every record
+    /// has the same size. This is the canonical on-disk representation and
+    /// is read back by [`read_from`](Self::read_from).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::{Hash, HASH_SIZE};
+    ///
+    /// let hash = Hash::from_slice(&[1, 2, 3]).unwrap();
+    /// let mut record = Vec::new();
+    /// hash.write_to(&mut record).unwrap();
+    /// assert_eq!(record.len(), HASH_SIZE);
+    /// ```
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        // Pad explicitly: `set_len` may leave stale bytes past `len`.
+        let mut record = [0u8; HASH_SIZE];
+        record[..self.len].copy_from_slice(self.as_bytes());
+        w.write_all(&record)
     }
-}
 
-/// Converts a hex character to its numeric value.
-#[inline]
-fn hex_digit_value(c: u8) -> Option<u8> {
-    match c {
-        b'0'..=b'9' => Some(c - b'0'),
-        b'a'..=b'f' => Some(c - b'a' + 10),
-        b'A'..=b'F' => Some(c - b'A' + 10),
-        _ => None,
+    /// Reads a fixed-width record of exactly [`HASH_SIZE`] bytes.
+    ///
+    /// The returned hash always has length [`HASH_SIZE`]; the original
+    /// length of a zero-padded partial hash is not recorded.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`io::ErrorKind::UnexpectedEof`] if fewer than
+    /// [`HASH_SIZE`] bytes are available.
+    pub fn read_from<R: Read>(r: &mut R) -> io::Result<Self> {
+        let mut bytes = [0u8; HASH_SIZE];
+        r.read_exact(&mut bytes)?;
+        Ok(Self::new(bytes))
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
-    #[test]
-    fn test_hash_size_constant() {
-        assert_eq!(HASH_SIZE, 924);
+    /// Returns `true` if `other` is byte-for-byte the same hash.
+    ///
+    /// This is `self == other`, named for exact-duplicate checks that do
+    /// not need a perceptual distance. Near-duplicates, such as re-encoded
+    /// copies of an image, are not exact duplicates; use
+    /// [`distance`](Self::distance) for those.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::Hash;
+    ///
+    /// let a = Hash::from_slice(&[1, 2, 3]).unwrap();
+    /// assert!(a.is_exact_dup(&a.clone()));
+    /// assert!(!a.is_exact_dup(&Hash::from_slice(&[1, 2, 4]).unwrap()));
+    /// ```
+    #[inline]
+    pub fn is_exact_dup(&self, other: &Hash) -> bool {
+        self == other
     }
 
-    #[test]
-    fn test_hash_new() {
-        let data = [0xAB; HASH_SIZE];
-        let hash = Hash::new(data);
-        assert_eq!(hash.len(), HASH_SIZE);
-        assert!(!hash.is_empty());
+    /// Computes the Euclidean (L2) distance to another hash.
+    ///
+    /// Each byte is treated as one dimension. Only the first
+    /// `min(self.len(), other.len())` bytes are compared. Smaller values mean
+    /// more similar images; identical hashes have a distance of 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::Hash;
+    ///
+    /// let a = Hash::from_slice(&[0, 0, 0]).unwrap();
+    /// let b = Hash::from_slice(&[3, 4, 0]).unwrap();
+    /// assert_eq!(a.distance(&b), 5.0);
+    /// ```
+    pub fn distance(&self, other: &Hash) -> f64 {
+        self.debug_assert_same_format(other);
+        l2_distance(self.as_bytes(), other.as_bytes())
     }
 
-    #[test]
-    fn test_hash_default() {
-        let hash = Hash::default();
-        assert!(hash.is_empty());
-        assert_eq!(hash.len(), HASH_SIZE);
+    /// Computes the exact squared Euclidean distance to another hash.
+    ///
+    /// This is the integer sum of squared byte differences over the same
+    /// bytes [`distance`](Self::distance) compares, and `distance` is its
+    /// square root. The sum is computed without floating point, so it is
+    /// identical on every platform. To threshold on integers, compare
+    /// against the square of the distance threshold: for a whole-number
+    /// threshold `t`, `distance < t` exactly when
+    /// `distance_squared_int < t * t`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::Hash;
+    ///
+    /// let a = Hash::from_slice(&[0, 0, 0]).unwrap();
+    /// let b = Hash::from_slice(&[3, 4, 0]).unwrap();
+    /// assert_eq!(a.distance_squared_int(&b), 25);
+    /// assert_eq!(a.distance(&b), (a.distance_squared_int(&b) as f64).sqrt());
+    /// ```
+    pub fn distance_squared_int(&self, other: &Hash) -> u64 {
+        self.debug_assert_same_format(other);
+        u64::from(squared_distance(self.as_bytes(), other.as_bytes()))
     }
 
-    #[test]
-    fn test_hash_from_slice() {
-        let data = [0xAB; 100];
-        let hash = Hash::from_slice(&data).unwrap();
-        assert_eq!(hash.len(), 100);
-        assert_eq!(&hash.as_bytes()[..100], &data);
+    /// Computes the Euclidean distance to another hash, treating the bytes
+    /// past the end of the shorter hash as zeros.
+    ///
+    /// Unlike [`distance`](Self::distance), which ignores the bytes only one
+    /// hash has, every byte of the longer hash contributes. Zero-padding is
+    /// one particular reading of a partial hash, not something the library
+    /// defines: a hash truncated in storage did not really end in zeros, so
+    /// the padded distance of a truncated copy from its original grows with
+    /// the size of the lost bytes. Use it only when the missing bytes are
+    /// known to be zero, or to deliberately penalize length mismatches.
+    /// For hashes of equal length both methods agree.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::Hash;
+    ///
+    /// let full = Hash::from_slice(&[1, 2, 3, 4]).unwrap();
+    /// let partial = Hash::from_slice(&[1, 2]).unwrap();
+    /// assert_eq!(full.distance(&partial), 0.0);
+    /// assert_eq!(full.distance_padded(&partial), 5.0);
+    /// ```
+    pub fn distance_padded(&self, other: &Hash) -> f64 {
+        self.debug_assert_same_format(other);
+        let (a, b) = (self.as_bytes(), other.as_bytes());
+        let (shorter, longer) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+        let tail: u32 = longer[shorter.len()..]
+            .iter()
+            .map(|&byte| u32::from(byte) * u32::from(byte))
+            .sum();
+        f64::from(squared_distance(a, b) + tail).sqrt()
     }
 
-    #[test]
-    fn test_hash_from_slice_too_large() {
-        let data = [0xAB; HASH_SIZE + 1];
-        assert!(Hash::from_slice(&data).is_none());
+    /// Panics in debug builds if `self` and `other` differ in
+    /// [`format`](Self::format), as when one was never decoded from Base64.
+    #[inline]
+    fn debug_assert_same_format(&self, other: &Hash) {
+        debug_assert_eq!(
+            self.format(),
+            other.format(),
+            "comparing hashes in different formats; decode Base64 hashes first"
+        );
     }
 
-    #[test]
-    fn test_hash_to_hex() {
+    /// Compares this hash with another and classifies the result.
+    ///
+    /// Byte-equal hashes are [`MatchVerdict::Identical`]. Otherwise the
+    /// [`distance`](Self::distance) is computed and the verdict is
+    /// [`MatchVerdict::Match`] when it is below `threshold`, or
+    /// [`MatchVerdict::NoMatch`] when it is not.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::{Hash, MatchVerdict};
+    ///
+    /// let a = Hash::from_slice(&[0, 0]).unwrap();
+    /// let b = Hash::from_slice(&[3, 4]).unwrap();
+    ///
+    /// assert_eq!(a.compare(&a, 1.0), MatchVerdict::Identical);
+    /// assert_eq!(a.compare(&b, 10.0), MatchVerdict::Match(5.0));
+    /// assert_eq!(a.compare(&b, 5.0), MatchVerdict::NoMatch(5.0));
+    /// ```
+    pub fn compare(&self, other: &Hash, threshold: f64) -> MatchVerdict {
+        if self.as_bytes() == other.as_bytes() {
+            return MatchVerdict::Identical;
+        }
+
+        let distance = self.distance(other);
+        if distance < threshold {
+            MatchVerdict::Match(distance)
+        } else {
+            MatchVerdict::NoMatch(distance)
+        }
+    }
+
+    /// Returns the distance to `other` rescaled so that 1 means identical
+    /// and 0 means as far apart as two hashes can be.
+    ///
+    /// This is `1 - distance / MAX_DISTANCE`, a linear rescaling of
+    /// [`distance`](Self::distance) with no notion of what counts as a
+    /// match: [`DEFAULT_MATCH_THRESHOLD`] corresponds to a similarity of
+    /// about 0.77, so most of the range separates unrelated images. For a
+    /// calibrated 0-to-1 score use
+    /// [`match_confidence`](Self::match_confidence) instead. Partial hashes
+    /// can't reach [`MAX_DISTANCE`], so their similarity never reaches 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::{Hash, HASH_SIZE};
+    ///
+    /// let black = Hash::new([0; HASH_SIZE]);
+    /// let white = Hash::new([255; HASH_SIZE]);
+    /// assert_eq!(black.similarity(&black), 1.0);
+    /// assert_eq!(black.similarity(&white), 0.0);
+    /// ```
+    pub fn similarity(&self, other: &Hash) -> f64 {
+        1.0 - self.distance(other) / MAX_DISTANCE
+    }
+
+    /// Estimates how likely it is that two hashes came from the same image,
+    /// as a value between 0 and 1.
+    ///
+    /// Uses [`ConfidenceCurve::default`], which gives 0.5 at
+    /// [`DEFAULT_MATCH_THRESHOLD`]. The calibration is a heuristic meant for
+    /// presenting results in a UI, not a measured probability. Use
+    /// [`match_confidence_with`](Self::match_confidence_with) to tune it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::{Hash, HASH_SIZE};
+    ///
+    /// let hash = Hash::new([100; HASH_SIZE]);
+    /// assert!(hash.match_confidence(&hash) > 0.99);
+    /// ```
+    pub fn match_confidence(&self, other: &Hash) -> f64 {
+        self.match_confidence_with(other, ConfidenceCurve::default())
+    }
+
+    /// Estimates the match confidence of two hashes with a custom curve.
+    ///
+    /// See [`match_confidence`](Self::match_confidence).
+    pub fn match_confidence_with(&self, other: &Hash, curve: ConfidenceCurve) -> f64 {
+        curve.confidence(self.distance(other))
+    }
+
+    /// Computes the byte-wise average of a set of hashes.
+    ///
+    /// Each byte position is averaged across all hashes and rounded to the
+    /// nearest integer (halves round up). The centroid is **not** a valid
+    /// PhotoDNA hash of any image, but it is a useful comparison anchor when
+    /// matching against a cluster of near-duplicates.
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if `hashes` is empty or the hashes have different
+    /// lengths.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::Hash;
+    ///
+    /// let a = Hash::from_slice(&[0, 10, 255]).unwrap();
+    /// let b = Hash::from_slice(&[3, 20, 255]).unwrap();
+    /// let center = Hash::centroid(&[a, b]).unwrap();
+    /// assert_eq!(center.as_bytes(), &[2, 15, 255]);
+    /// ```
+    pub fn centroid(hashes: &[Hash]) -> Option<Hash> {
+        let (first, rest) = hashes.split_first()?;
+        let len = first.len;
+        if rest.iter().any(|h| h.len != len) {
+            return None;
+        }
+
+        let mut sums = [0u64; HASH_SIZE];
+        for hash in hashes {
+            for (sum, &byte) in sums.iter_mut().zip(hash.as_bytes()) {
+                *sum += u64::from(byte);
+            }
+        }
+
+        let count = hashes.len() as u64;
+        let mut bytes = [0u8; HASH_SIZE];
+        for (byte, sum) in bytes[..len].iter_mut().zip(&sums) {
+            *byte = ((sum + count / 2) / count) as u8;
+        }
+
+        Some(Self { bytes, len })
+    }
+
+    /// Computes a coarse 64-bit summary of the hash for prefiltering.
+    ///
+    /// The hash bytes are split into 65 consecutive segments, and bit `i`
+    /// is set when segment `i` has a larger mean than segment `i + 1`, in
+    /// the style of a difference hash. Hashes a small
+    /// [`distance`](Self::distance) apart usually have keys a few bits apart
+    /// (see [`u64::count_ones`] on the XOR of two keys), while unrelated
+    /// hashes differ in about half of the bits.
+    ///
+    /// This is a heuristic: two hashes within a matching threshold can
+    /// still have distant keys, so pruning by key can miss matches.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::{Hash, HASH_SIZE};
+    ///
+    /// let mut bytes = [0u8; HASH_SIZE];
+    /// bytes[..HASH_SIZE / 2].fill(200);
+    /// let a = Hash::new(bytes);
+    /// bytes[0] = 190;
+    /// let b = Hash::new(bytes);
+    ///
+    /// assert_eq!(a.prefilter_key(), b.prefilter_key());
+    /// ```
+    pub fn prefilter_key(&self) -> u64 {
+        const SEGMENTS: usize = 65;
+
+        let bytes = self.as_bytes();
+        let mut segments = [(0u64, 0u64); SEGMENTS];
+        for (i, (sum, count)) in segments.iter_mut().enumerate() {
+            let segment = &bytes[i * bytes.len() / SEGMENTS..(i + 1) * bytes.len() / SEGMENTS];
+            *sum = segment.iter().map(|&b| u64::from(b)).sum();
+            *count = segment.len() as u64;
+        }
+
+        segments.windows(2).enumerate().fold(0, |key, (i, pair)| {
+            let ((sum_a, count_a), (sum_b, count_b)) = (pair[0], pair[1]);
+            // Compare means without dividing: segments differ in length
+            // by at most one byte.
+            if sum_a * count_b > sum_b * count_a {
+                key | (1 << i)
+            } else {
+                key
+            }
+        })
+    }
+
+    /// Returns the shard, in `0..shards`, that this hash belongs to.
+    ///
+    /// The key is derived from a 64-bit FNV-1a fingerprint of the hash
+    /// bytes, so it is the same on every run, platform and crate version,
+    /// and spreads hashes evenly across shards. Use it to route entries and
+    /// queries for an exact hash to the same machine.
+    ///
+    /// This is for partitioning only: near-duplicate hashes almost always
+    /// land on different shards, so a similarity query must still be sent
+    /// to every shard. See [`prefilter_key`](Self::prefilter_key) for a key
+    /// that keeps similar hashes close.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::{Hash, HASH_SIZE};
+    ///
+    /// let hash = Hash::new([42; HASH_SIZE]);
+    /// let shard = hash.shard_key(16);
+    /// assert!(shard < 16);
+    /// assert_eq!(hash.shard_key(16), shard);
+    /// ```
+    pub fn shard_key(&self, shards: u32) -> u32 {
+        const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        assert!(shards > 0, "shard count must be positive");
+        let fingerprint = self.as_bytes().iter().fold(FNV_OFFSET, |h, &b| {
+            (h ^ u64::from(b)).wrapping_mul(FNV_PRIME)
+        });
+        (fingerprint % u64::from(shards)) as u32
+    }
+
+    /// Returns a mutable slice to the entire hash buffer.
+    ///
+    /// This is useful for passing to FFI functions that write directly
+    /// to the buffer.
+    ///
+    /// # Safety
+    ///
+    /// After modifying the buffer via this method, you may need to update
+    /// the logical length using [`set_len`](Self::set_len) if the actual
+    /// data size has changed.
+    #[inline]
+    pub fn as_mut_bytes(&mut self) -> &mut [u8; HASH_SIZE] {
+        &mut self.bytes
+    }
+
+    /// Sets the length of valid hash data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len > HASH_SIZE`.
+    #[inline]
+    pub fn set_len(&mut self, len: usize) {
+        assert!(len <= HASH_SIZE, "length exceeds maximum hash size");
+        self.len = len;
+    }
+
+    /// Fills the hash buffer through `f` and sets the length it reports.
+    ///
+    /// `f` receives the whole buffer and returns how many bytes it wrote.
+    /// This keeps the write and the length update together, so FFI callers
+    /// can't forget to call [`set_len`](Self::set_len) afterwards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` returns more than `HASH_SIZE`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::Hash;
+    ///
+    /// let mut hash = Hash::zeroed();
+    /// hash.write_with(|buf| {
+    ///     buf[..4].copy_from_slice(&[1, 2, 3, 4]);
+    ///     4
+    /// });
+    /// assert_eq!(hash.as_bytes(), &[1, 2, 3, 4]);
+    /// ```
+    #[inline]
+    pub fn write_with<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut [u8; HASH_SIZE]) -> usize,
+    {
+        let written = f(&mut self.bytes);
+        self.set_len(written);
+    }
+
+    /// Creates a new hash with uninitialized content.
+    ///
+    /// This is useful for performance-critical code where the hash
+    /// will be immediately overwritten by FFI.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure the hash is fully initialized before
+    /// reading from it.
+    #[inline]
+    pub const fn zeroed() -> Self {
+        Self {
+            bytes: [0u8; HASH_SIZE],
+            len: 0,
+        }
+    }
+}
+
+impl Default for Hash {
+    fn default() -> Self {
+        Self {
+            bytes: [0u8; HASH_SIZE],
+            len: HASH_SIZE,
+        }
+    }
+}
+
+/// Shows the first 16 bytes as hex, or every byte with the alternate
+/// flag (`{:#?}`).
+///
+/// `assert_eq!` uses the short form; compare [`to_hex`](Hash::to_hex)
+/// strings instead when a failure needs the full hashes.
+impl fmt::Debug for Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            return write!(f, "Hash({})", self.to_hex());
+        }
+
+        // Show first 16 bytes as hex for readability
+        let preview_len = 16.min(self.len);
+        let preview: String = self.bytes[..preview_len]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+
+        if self.len > preview_len {
+            write!(f, "Hash({}..., {} bytes)", preview, self.len)
+        } else {
+            write!(f, "Hash({})", preview)
+        }
+    }
+}
+
+impl fmt::Display for Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+impl AsRef<[u8]> for Hash {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl<'a> IntoIterator for &'a Hash {
+    type Item = &'a u8;
+    type IntoIter = std::slice::Iter<'a, u8>;
+
+    /// Iterates over the valid bytes, as returned by [`Hash::as_bytes`].
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_bytes().iter()
+    }
+}
+
+impl From<[u8; HASH_SIZE]> for Hash {
+    fn from(bytes: [u8; HASH_SIZE]) -> Self {
+        Self::new(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for Hash {
+    type Error = ();
+
+    fn try_from(slice: &[u8]) -> Result<Self, Self::Error> {
+        Self::from_slice(slice).ok_or(())
+    }
+}
+
+impl TryFrom<&photodna_sys::HashResult> for Hash {
+    type Error = PhotoDnaError;
+
+    /// Extracts the hash from a border-detection result.
+    ///
+    /// Negative result codes are converted to the matching
+    /// [`PhotoDnaError`]. The packed struct's fields are copied out before
+    /// use, so this is safe to call on results straight from FFI.
+    fn try_from(result: &photodna_sys::HashResult) -> Result<Self, Self::Error> {
+        // Copy packed field to avoid unaligned access
+        let result_code = result.result;
+        if result_code < 0 {
+            return Err(PhotoDnaError::from_error_code(result_code));
+        }
+
+        // The hash is stored in the first HASH_SIZE bytes
+        let mut bytes = [0u8; HASH_SIZE];
+        bytes.copy_from_slice(&result.hash[..HASH_SIZE]);
+
+        Ok(Self::new(bytes))
+    }
+}
+
+/// Converts a hex character to its numeric value.
+#[inline]
+fn hex_digit_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Computes the Euclidean (L2) distance between two raw hashes.
+///
+/// The same computation as [`Hash::distance`], for hashes held as byte
+/// slices (for example rows read from a database) that would otherwise
+/// have to be copied into a [`Hash`](struct@Hash) first. Only the first
+/// `min(a.len(), b.len())` bytes are compared.
+///
+/// # Panics
+///
+/// Panics if either slice is empty.
+///
+/// # Examples
+///
+/// ```rust
+/// assert_eq!(photodna::distance_bytes(&[0, 0, 0], &[3, 4, 0]), 5.0);
+/// ```
+pub fn distance_bytes(a: &[u8], b: &[u8]) -> f64 {
+    assert!(
+        !a.is_empty() && !b.is_empty(),
+        "distance_bytes requires non-empty hashes"
+    );
+    l2_distance(a, b)
+}
+
+/// Euclidean distance over the first `min(a.len(), b.len())` bytes.
+pub(crate) fn l2_distance(a: &[u8], b: &[u8]) -> f64 {
+    f64::from(squared_distance(a, b)).sqrt()
+}
+
+/// Squared Euclidean distance over the first `min(a.len(), b.len())` bytes.
+///
+/// Written with 32-bit lanes so the loop autovectorizes. The sum cannot
+/// overflow: it is at most `HASH_SIZE * 255²`, well below `u32::MAX`.
+#[inline]
+pub(crate) fn squared_distance(a: &[u8], b: &[u8]) -> u32 {
+    const LANES: usize = 16;
+
+    let len = a.len().min(b.len());
+    let (a, b) = (&a[..len], &b[..len]);
+    let square = |x: u8, y: u8| {
+        let d = i32::from(x) - i32::from(y);
+        (d * d) as u32
+    };
+
+    let mut lanes = [0u32; LANES];
+    let (chunks_a, chunks_b) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+    let tail: u32 = chunks_a
+        .remainder()
+        .iter()
+        .zip(chunks_b.remainder())
+        .map(|(&x, &y)| square(x, y))
+        .sum();
+    for (ca, cb) in chunks_a.zip(chunks_b) {
+        for i in 0..LANES {
+            lanes[i] += square(ca[i], cb[i]);
+        }
+    }
+    lanes.iter().sum::<u32>() + tail
+}
+
+/// The representation held in a [`Hash`](struct@Hash) buffer, as reported by
+/// [`Hash::format`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashFormat {
+    /// Binary hash data, the only format distances are defined for.
+    Binary,
+    /// Undecoded Base64 text. Decode it with [`Hash::from_slice_max`] or
+    /// [`Hash::from_base64`] before comparing.
+    Base64Text,
+}
+
+/// A text encoding for hashes.
+///
+/// # Examples
+///
+/// ```rust
+/// use photodna::{Hash, HashEncoding};
+///
+/// let hash = Hash::from_slice(&[0xAB, 0xCD]).unwrap();
+/// assert_eq!(HashEncoding::Hex.encode(&hash), "abcd");
+/// assert_eq!(HashEncoding::Base64.decode("q80="), Some(hash));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum HashEncoding {
+    /// Lowercase hexadecimal, as produced by [`Hash::to_hex`].
+    #[default]
+    Hex,
+    /// Standard padded Base64, as produced by [`Hash::to_base64`].
+    Base64,
+}
+
+impl HashEncoding {
+    /// Encodes a hash as text.
+    pub fn encode(self, hash: &Hash) -> String {
+        match self {
+            Self::Hex => hash.to_hex(),
+            Self::Base64 => hash.to_base64(),
+        }
+    }
+
+    /// Decodes a hash from text, returning `None` if it is malformed.
+    pub fn decode(self, text: &str) -> Option<Hash> {
+        match self {
+            Self::Hex => Hash::from_hex(text),
+            Self::Base64 => Hash::from_base64(text),
+        }
+    }
+}
+
+/// A hash paired with its precomputed squared L2 norm.
+///
+/// Distances between prepared hashes of equal length use the expansion
+/// `‖a − b‖² = ‖a‖² + ‖b‖² − 2·a·b`, so matching one query against many
+/// stored hashes only computes a dot product per pair. The arithmetic is
+/// exact integer math, so results are identical to [`Hash::distance`].
+///
+/// [`Hash::distance`] is vectorized, so it is the faster choice for bulk
+/// matching. Comparing one query against 10,000 random full hashes
+/// (`benches/distance.rs`, x86-64, release build, no `target-cpu` flags)
+/// took about 6.1 ms with [`distance_to`](Self::distance_to), 3.0 ms with
+/// [`Hash::distance`] and 9.2 ms with a byte-by-byte `u64` loop.
+///
+/// # Examples
+///
+/// ```rust
+/// use photodna::{Hash, PreparedHash};
+///
+/// let a = PreparedHash::new(Hash::from_slice(&[0, 0]).unwrap());
+/// let b = PreparedHash::new(Hash::from_slice(&[3, 4]).unwrap());
+/// assert_eq!(a.distance_to(&b), 5.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreparedHash {
+    /// The underlying hash.
+    hash: Hash,
+    /// Sum of the squares of the valid hash bytes.
+    norm_squared: u64,
+}
+
+impl PreparedHash {
+    /// Prepares a hash by computing its squared norm.
+    pub fn new(hash: Hash) -> Self {
+        let norm_squared = hash
+            .as_bytes()
+            .iter()
+            .map(|&b| u64::from(b) * u64::from(b))
+            .sum();
+        Self { hash, norm_squared }
+    }
+
+    /// Returns the underlying hash.
+    #[inline]
+    pub fn hash(&self) -> &Hash {
+        &self.hash
+    }
+
+    /// Returns the squared L2 norm of the hash bytes.
+    #[inline]
+    pub fn norm_squared(&self) -> u64 {
+        self.norm_squared
+    }
+
+    /// Computes the Euclidean distance to another prepared hash.
+    ///
+    /// Equal to [`Hash::distance`] on the underlying hashes. Hashes of
+    /// different lengths fall back to the direct computation, since the
+    /// cached norms cover each hash's full length.
+    pub fn distance_to(&self, query: &PreparedHash) -> f64 {
+        if self.hash.len() != query.hash.len() {
+            return self.hash.distance(&query.hash);
+        }
+
+        let dot: u64 = self
+            .hash
+            .as_bytes()
+            .iter()
+            .zip(query.hash.as_bytes())
+            .map(|(&a, &b)| u64::from(a) * u64::from(b))
+            .sum();
+        ((self.norm_squared + query.norm_squared - 2 * dot) as f64).sqrt()
+    }
+}
+
+impl From<Hash> for PreparedHash {
+    fn from(hash: Hash) -> Self {
+        Self::new(hash)
+    }
+}
+
+/// The standard Base64 alphabet (RFC 4648).
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Converts a Base64 character to its numeric value.
+#[inline]
+fn base64_digit_value(c: u8) -> Option<u8> {
+    match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+/// Returns the number of bytes a padded Base64 string decodes to.
+#[inline]
+fn base64_decoded_len(text: &str) -> usize {
+    let padding = text.bytes().rev().take_while(|&c| c == b'=').count().min(2);
+    (text.len() / 4 * 3).saturating_sub(padding)
+}
+
+/// The outcome of comparing two hashes with [`Hash::compare`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchVerdict {
+    /// The hashes are byte-for-byte equal.
+    Identical,
+    /// The hashes differ, but their distance is below the threshold.
+    Match(f64),
+    /// The distance is at or above the threshold.
+    NoMatch(f64),
+}
+
+impl MatchVerdict {
+    /// Returns `true` for [`Identical`](Self::Identical) and
+    /// [`Match`](Self::Match) verdicts.
+    #[inline]
+    pub fn is_match(&self) -> bool {
+        !matches!(self, Self::NoMatch(_))
+    }
+
+    /// Returns the distance between the hashes (0 for identical hashes).
+    #[inline]
+    pub fn distance(&self) -> f64 {
+        match *self {
+            Self::Identical => 0.0,
+            Self::Match(d) | Self::NoMatch(d) => d,
+        }
+    }
+}
+
+/// A logistic curve mapping hash distances to match confidences.
+///
+/// The confidence for a distance `d` is
+/// `1 / (1 + exp((d - midpoint) / scale))`: 0.5 at `midpoint`, close to 1
+/// well below it and close to 0 well above it. `scale` sets how gradual
+/// the transition is: the confidence falls from about 0.73 to about 0.27
+/// between one `scale` below and one `scale` above the midpoint.
+///
+/// # Examples
+///
+/// ```rust
+/// use photodna::ConfidenceCurve;
+///
+/// let strict = ConfidenceCurve::logistic(1000.0, 100.0);
+/// assert_eq!(strict.confidence(1000.0), 0.5);
+/// assert!(strict.confidence(1500.0) < 0.01);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfidenceCurve {
+    /// The distance with a confidence of 0.5.
+    pub midpoint: f64,
+    /// The width of the transition from high to low confidence.
+    pub scale: f64,
+}
+
+impl ConfidenceCurve {
+    /// Creates a logistic curve centred on `midpoint`.
+    #[inline]
+    pub const fn logistic(midpoint: f64, scale: f64) -> Self {
+        Self { midpoint, scale }
+    }
+
+    /// Returns the confidence, between 0 and 1, for a distance.
+    pub fn confidence(&self, distance: f64) -> f64 {
+        1.0 / (1.0 + ((distance - self.midpoint) / self.scale).exp())
+    }
+}
+
+impl Default for ConfidenceCurve {
+    /// Centred on [`DEFAULT_MATCH_THRESHOLD`] with a scale of 200, so
+    /// identical hashes score above 0.9998 and distances past 3000 score
+    /// below 0.003.
+    fn default() -> Self {
+        Self::logistic(DEFAULT_MATCH_THRESHOLD, 200.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_confidence() {
+        let zero = Hash::new([0; HASH_SIZE]);
+        assert!(zero.match_confidence(&zero) > 0.9998);
+
+        // 81 bytes differing by 200 are exactly 1800 apart.
+        let mut bytes = [0u8; HASH_SIZE];
+        bytes[..81].fill(200);
+        let at_threshold = Hash::new(bytes);
+        assert_eq!(zero.distance(&at_threshold), DEFAULT_MATCH_THRESHOLD);
+        assert_eq!(zero.match_confidence(&at_threshold), 0.5);
+
+        let far = Hash::new([255; HASH_SIZE]);
+        assert!(zero.match_confidence(&far) < 1e-10);
+
+        let strict = ConfidenceCurve::logistic(900.0, 100.0);
+        assert!(zero.match_confidence_with(&at_threshold, strict) < 1e-3);
+    }
+
+    #[test]
+    fn test_hash_size_constant() {
+        assert_eq!(HASH_SIZE, 924);
+    }
+
+    #[test]
+    fn test_hash_new() {
+        let data = [0xAB; HASH_SIZE];
+        let hash = Hash::new(data);
+        assert_eq!(hash.len(), HASH_SIZE);
+        assert!(!hash.is_empty());
+    }
+
+    #[test]
+    fn test_hash_default() {
+        let hash = Hash::default();
+        assert!(hash.is_empty());
+        assert_eq!(hash.len(), HASH_SIZE);
+    }
+
+    #[test]
+    fn test_hash_from_slice() {
+        let data = [0xAB; 100];
+        let hash = Hash::from_slice(&data).unwrap();
+        assert_eq!(hash.len(), 100);
+        assert_eq!(&hash.as_bytes()[..100], &data);
+    }
+
+    #[test]
+    fn test_format() {
+        let binary = Hash::new([0xAB; HASH_SIZE]);
+        assert_eq!(binary.format(), HashFormat::Binary);
+
+        let text = binary.to_base64();
+        let raw = Hash::from_slice(&text.as_bytes()[..HASH_SIZE]).unwrap();
+        assert_eq!(raw.format(), HashFormat::Base64Text);
+        assert_eq!(
+            Hash::from_slice_max(text.as_bytes()).unwrap().format(),
+            HashFormat::Binary
+        );
+
+        // Short hashes are never mistaken for text.
+        assert_eq!(
+            Hash::from_slice(b"QUJD").unwrap().format(),
+            HashFormat::Binary
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "different formats")]
+    fn test_distance_rejects_mixed_formats() {
+        let binary = Hash::new([0xAB; HASH_SIZE]);
+        let text = binary.to_base64();
+        let raw = Hash::from_slice(&text.as_bytes()[..HASH_SIZE]).unwrap();
+        binary.distance(&raw);
+    }
+
+    #[test]
+    fn test_distance_bytes_matches_hash_distance() {
+        let mut rng = 0x2545_F491_4F6C_DD1Du64;
+        let mut random_bytes = |len: usize| {
+            (0..len)
+                .map(|_| {
+                    rng ^= rng << 13;
+                    rng ^= rng >> 7;
+                    rng ^= rng << 17;
+                    rng as u8
+                })
+                .collect::<Vec<u8>>()
+        };
+        for (len_a, len_b) in [(HASH_SIZE, HASH_SIZE), (HASH_SIZE, 100), (3, 17)] {
+            let (a, b) = (random_bytes(len_a), random_bytes(len_b));
+            let expected = Hash::from_slice(&a)
+                .unwrap()
+                .distance(&Hash::from_slice(&b).unwrap());
+            assert_eq!(distance_bytes(&a, &b), expected);
+        }
+    }
+
+    #[test]
+    fn test_distance_squared_int() {
+        let a: Vec<u8> = (0..HASH_SIZE).map(|i| (i * 7) as u8).collect();
+        let b: Vec<u8> = (0..HASH_SIZE).map(|i| (i * 13 + 5) as u8).collect();
+        let expected: u64 = a
+            .iter()
+            .zip(&b)
+            .map(|(&x, &y)| (i64::from(x) - i64::from(y)).pow(2) as u64)
+            .sum();
+
+        let (a, b) = (Hash::from_slice(&a).unwrap(), Hash::from_slice(&b).unwrap());
+        assert_eq!(a.distance_squared_int(&b), expected);
+        assert_eq!(b.distance_squared_int(&a), expected);
+        assert_eq!(a.distance(&b), (expected as f64).sqrt());
+        assert_eq!(a.distance_squared_int(&a), 0);
+
+        let max = Hash::new([0xFF; HASH_SIZE]);
+        assert_eq!(
+            max.distance_squared_int(&Hash::new([0; HASH_SIZE])),
+            HASH_SIZE as u64 * 255 * 255
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "non-empty")]
+    fn test_distance_bytes_rejects_empty() {
+        distance_bytes(&[], &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_write_with_sets_len() {
+        let mut hash = Hash::new([0xFF; HASH_SIZE]);
+        hash.write_with(|buf| {
+            buf[..3].copy_from_slice(&[1, 2, 3]);
+            3
+        });
+        assert_eq!(hash.len(), 3);
+        assert_eq!(hash.as_bytes(), &[1, 2, 3]);
+
+        hash.write_with(|_| HASH_SIZE);
+        assert!(hash.is_full());
+    }
+
+    #[test]
+    #[should_panic(expected = "length exceeds maximum hash size")]
+    fn test_write_with_rejects_overlong_len() {
+        Hash::zeroed().write_with(|_| HASH_SIZE + 1);
+    }
+
+    #[test]
+    fn test_hash_from_slice_too_large() {
+        let data = [0xAB; HASH_SIZE + 1];
+        assert!(Hash::from_slice(&data).is_none());
+    }
+
+    #[test]
+    fn test_hash_from_exact() {
+        assert!(Hash::from_exact(&[0xAB; HASH_SIZE - 1]).is_none());
+        assert!(Hash::from_exact(&[0xAB; HASH_SIZE + 1]).is_none());
+
+        let hash = Hash::from_exact(&[0xAB; HASH_SIZE]).unwrap();
+        assert!(hash.is_full());
+        assert!(!Hash::from_slice(&[0xAB; HASH_SIZE - 1]).unwrap().is_full());
+    }
+
+    #[test]
+    fn test_hash_to_hex() {
         let data = [0xAB, 0xCD, 0xEF, 0x01];
         let hash = Hash::from_slice(&data).unwrap();
         assert_eq!(hash.to_hex(), "abcdef01");
@@ -390,6 +1529,45 @@ mod tests {
         assert!(Hash::from_hex("ghij").is_none()); // Invalid chars
     }
 
+    #[test]
+    fn test_hash_eq_hex() {
+        let hash = Hash::new(std::array::from_fn(|i| i as u8));
+        assert!(hash.eq_hex(&hash.to_hex()));
+        assert!(hash.eq_hex(&hash.to_hex_upper()));
+
+        let mut other = hash.to_hex();
+        other.replace_range(..2, "ff");
+        assert!(!hash.eq_hex(&other));
+        assert!(!hash.eq_hex(&hash.to_hex()[..20]));
+        assert!(!hash.eq_hex("zz"));
+        assert!(Hash::from_slice(&[]).unwrap().eq_hex(""));
+    }
+
+    #[test]
+    fn test_hash_to_vec_and_into_array() {
+        let full = Hash::new([0xAB; HASH_SIZE]);
+        assert_eq!(full.to_vec(), vec![0xAB; HASH_SIZE]);
+        assert_eq!(full.into_array(), [0xAB; HASH_SIZE]);
+
+        let mut partial = full;
+        partial.set_len(10);
+        assert_eq!(partial.to_vec(), vec![0xAB; 10]);
+        let array = partial.into_array();
+        assert!(array[..10].iter().all(|&b| b == 0xAB));
+        assert!(array[10..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_hash_into_iterator() {
+        let hash = Hash::from_slice(&[1, 2, 3]).unwrap();
+        let mut sum = 0u32;
+        for &byte in &hash {
+            sum += u32::from(byte);
+        }
+        assert_eq!(sum, 6);
+        assert_eq!((&hash).into_iter().count(), hash.len());
+    }
+
     #[test]
     fn test_hash_copy() {
         let hash1 = Hash::from_slice(&[1, 2, 3, 4]).unwrap();
@@ -403,6 +1581,18 @@ mod tests {
         let debug = format!("{:?}", hash);
         assert!(debug.contains("Hash("));
         assert!(debug.contains("20 bytes"));
+
+        let full = format!("{:#?}", hash);
+        assert_eq!(full, format!("Hash({})", "ab".repeat(20)));
+    }
+
+    #[test]
+    fn test_max_distance() {
+        let zero = Hash::new([0; HASH_SIZE]);
+        let ff = Hash::new([0xFF; HASH_SIZE]);
+        assert_eq!(zero.distance(&ff), MAX_DISTANCE);
+        assert_eq!(zero.similarity(&ff), 0.0);
+        assert_eq!(ff.similarity(&ff), 1.0);
     }
 
     #[test]
@@ -421,6 +1611,36 @@ mod tests {
         assert_ne!(hash1, hash3);
     }
 
+    #[test]
+    fn test_hash_write_read_roundtrip() {
+        let hash = Hash::from_slice(&[0x5A; HASH_SIZE]).unwrap();
+        let mut record = Vec::new();
+        hash.write_to(&mut record).unwrap();
+        assert_eq!(record.len(), HASH_SIZE);
+
+        let read = Hash::read_from(&mut record.as_slice()).unwrap();
+        assert_eq!(read, hash);
+    }
+
+    #[test]
+    fn test_hash_write_read_partial() {
+        let hash = Hash::from_slice(&[1, 2, 3, 4]).unwrap();
+        let mut record = Vec::new();
+        hash.write_to(&mut record).unwrap();
+        assert_eq!(record.len(), HASH_SIZE);
+        assert!(record[4..].iter().all(|&b| b == 0));
+
+        let read = Hash::read_from(&mut record.as_slice()).unwrap();
+        assert_eq!(read.len(), HASH_SIZE);
+        assert_eq!(&read.as_bytes()[..4], hash.as_bytes());
+    }
+
+    #[test]
+    fn test_hash_read_short_record() {
+        let err = Hash::read_from(&mut [0u8; 10].as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
     #[test]
     fn test_hash_in_hashset() {
         use std::collections::HashSet;
@@ -432,4 +1652,186 @@ mod tests {
         assert!(set.contains(&Hash::from_slice(&[1, 2, 3]).unwrap()));
         assert!(!set.contains(&Hash::from_slice(&[7, 8, 9]).unwrap()));
     }
+
+    #[test]
+    fn test_hash_distance_padded() {
+        let full = Hash::new([3; HASH_SIZE]);
+        let partial = Hash::from_slice(&[3; 100]).unwrap();
+
+        // Truncation hides the missing bytes; padding counts them.
+        assert_eq!(full.distance(&partial), 0.0);
+        let expected = (9.0 * (HASH_SIZE - 100) as f64).sqrt();
+        assert!((full.distance_padded(&partial) - expected).abs() < 1e-9);
+        assert_eq!(
+            full.distance_padded(&partial),
+            partial.distance_padded(&full)
+        );
+
+        let other = Hash::new([5; HASH_SIZE]);
+        assert_eq!(full.distance_padded(&other), full.distance(&other));
+        let empty = Hash::from_slice(&[]).unwrap();
+        assert_eq!(empty.distance_padded(&empty), 0.0);
+    }
+
+    #[test]
+    fn test_hash_distance() {
+        let a = Hash::new([10; HASH_SIZE]);
+        assert_eq!(a.distance(&a), 0.0);
+
+        let b = Hash::new([12; HASH_SIZE]);
+        let expected = (4.0 * HASH_SIZE as f64).sqrt();
+        assert!((a.distance(&b) - expected).abs() < 1e-9);
+        assert_eq!(a.distance(&b), b.distance(&a));
+
+        // Only the common prefix is compared.
+        let short = Hash::from_slice(&[10, 10, 13]).unwrap();
+        assert_eq!(a.distance(&short), 3.0);
+    }
+
+    #[test]
+    fn test_hash_compare_verdicts() {
+        let a = Hash::new([0; HASH_SIZE]);
+        let mut bytes = [0; HASH_SIZE];
+        bytes[0] = 6;
+        bytes[1] = 8;
+        let b = Hash::new(bytes);
+
+        assert_eq!(a.compare(&a, 0.0), MatchVerdict::Identical);
+        assert_eq!(a.compare(&b, 10.5), MatchVerdict::Match(10.0));
+        assert_eq!(a.compare(&b, 10.0), MatchVerdict::NoMatch(10.0));
+
+        assert!(MatchVerdict::Identical.is_match());
+        assert!(!MatchVerdict::NoMatch(3.0).is_match());
+        assert_eq!(MatchVerdict::Identical.distance(), 0.0);
+        assert_eq!(MatchVerdict::Match(2.5).distance(), 2.5);
+    }
+
+    #[test]
+    fn test_hash_base64_roundtrip() {
+        for len in [0, 1, 2, 3, 4, 100, HASH_SIZE] {
+            let data: Vec<u8> = (0..len).map(|i| (i * 37 % 256) as u8).collect();
+            let hash = Hash::from_slice(&data).unwrap();
+            let encoded = hash.to_base64();
+            assert_eq!(encoded.len(), (len + 2) / 3 * 4);
+            assert_eq!(Hash::from_base64(&encoded).unwrap(), hash);
+        }
+
+        assert_eq!(Hash::from_slice(b"foobar").unwrap().to_base64(), "Zm9vYmFy");
+        assert!(Hash::from_base64("Zm9v!mFy").is_none());
+        assert!(Hash::from_base64("Zm9").is_none());
+        assert!(Hash::from_base64("Zg==Zg==").is_none());
+    }
+
+    #[test]
+    fn test_hash_from_slice_max_boundaries() {
+        // Exactly HASH_SIZE bytes is binary.
+        let binary = Hash::from_slice_max(&[0xAB; HASH_SIZE]).unwrap();
+        assert_eq!(binary, Hash::new([0xAB; HASH_SIZE]));
+
+        // Exactly HASH_SIZE_MAX bytes is Base64 and decodes to a full hash.
+        let encoded = binary.to_base64();
+        assert_eq!(encoded.len(), HASH_SIZE_MAX);
+        let decoded = Hash::from_slice_max(encoded.as_bytes()).unwrap();
+        assert_eq!(decoded, binary);
+
+        // Over-long and non-Base64 buffers are rejected with a reason.
+        assert_eq!(
+            Hash::from_slice_max(&[b'A'; HASH_SIZE_MAX + 1]),
+            Err(PhotoDnaError::InvalidHash)
+        );
+        assert_eq!(
+            Hash::from_slice_max(&[0xFF; HASH_SIZE_MAX]),
+            Err(PhotoDnaError::HashFormatInvalidCharacters)
+        );
+        assert_eq!(
+            Hash::from_slice_max(&[b'A'; HASH_SIZE + 1]),
+            Err(PhotoDnaError::InvalidHash)
+        );
+    }
+
+    #[test]
+    fn test_hash_prefilter_key() {
+        assert_eq!(Hash::new([7; HASH_SIZE]).prefilter_key(), 0);
+
+        let mut falling = [0u8; HASH_SIZE];
+        for (i, byte) in falling.iter_mut().enumerate() {
+            *byte = 255 - (i / 4) as u8;
+        }
+        assert_eq!(Hash::new(falling).prefilter_key(), u64::MAX);
+
+        // Hashes shorter than the segment count leave segments empty.
+        assert_eq!(Hash::from_slice(&[9, 1]).unwrap().prefilter_key(), 0);
+        assert_eq!(Hash::from_slice(&[]).unwrap().prefilter_key(), 0);
+    }
+
+    #[test]
+    fn test_hash_shard_key() {
+        // Pinned so that a change to the derivation is caught: shard
+        // assignments must not move between releases.
+        assert_eq!(Hash::from_slice(&[]).unwrap().shard_key(1000), 37);
+        assert_eq!(Hash::new([0; HASH_SIZE]).shard_key(u32::MAX), 452_987_065);
+
+        let shards = 8;
+        let samples = 8000;
+        let mut counts = [0u32; 8];
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        for _ in 0..samples {
+            let mut bytes = [0u8; HASH_SIZE];
+            for byte in &mut bytes {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                *byte = state as u8;
+            }
+            counts[Hash::new(bytes).shard_key(shards) as usize] += 1;
+        }
+        let expected = samples / shards;
+        for count in counts {
+            assert!(count.abs_diff(expected) < expected / 10, "{counts:?}");
+        }
+    }
+
+    #[test]
+    fn test_hash_centroid() {
+        let a = Hash::new([10; HASH_SIZE]);
+        let b = Hash::new([21; HASH_SIZE]);
+        let center = Hash::centroid(&[a, b]).unwrap();
+        assert_eq!(center, Hash::new([16; HASH_SIZE]));
+        assert_eq!(Hash::centroid(&[a]).unwrap(), a);
+
+        assert!(Hash::centroid(&[]).is_none());
+        let short = Hash::from_slice(&[1, 2, 3]).unwrap();
+        assert!(Hash::centroid(&[a, short]).is_none());
+    }
+
+    #[test]
+    fn test_hash_try_from_hash_result() {
+        let mut result = photodna_sys::HashResult {
+            result: 2,
+            ..Default::default()
+        };
+        result.hash[..HASH_SIZE].fill(0x5A);
+        result.hash[HASH_SIZE..].fill(0xFF);
+
+        let hash = Hash::try_from(&result).unwrap();
+        assert_eq!(hash, Hash::new([0x5A; HASH_SIZE]));
+
+        result.result = photodna_sys::PhotoDna_ErrorImageIsFlat;
+        assert_eq!(Hash::try_from(&result), Err(PhotoDnaError::ImageIsFlat));
+    }
+
+    #[test]
+    fn test_prepared_hash_matches_distance() {
+        let a = Hash::new(std::array::from_fn(|i| (i * 7 % 256) as u8));
+        let b = Hash::new(std::array::from_fn(|i| (i * 13 % 251) as u8));
+        let (pa, pb) = (PreparedHash::new(a), PreparedHash::new(b));
+
+        assert_eq!(pa.distance_to(&pb), a.distance(&b));
+        assert_eq!(pa.distance_to(&pa), 0.0);
+        assert_eq!(pa.hash(), &a);
+
+        // Mixed lengths fall back to the common-prefix distance.
+        let short = PreparedHash::new(Hash::from_slice(&a.as_bytes()[..10]).unwrap());
+        assert_eq!(pa.distance_to(&short), 0.0);
+    }
 }
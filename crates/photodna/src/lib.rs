@@ -16,8 +16,14 @@
 //! | [`Generator`] | Loads the PhotoDNA library and computes hashes |
 //! | [`Hash`][struct@Hash] | 924-byte perceptual hash with zero-copy semantics |
 //! | [`PixelFormat`] | Specifies input image pixel layout (RGB, RGBA, etc.) |
+//! | [`ChannelOrder`] | Channel order of packed RGB pixels (RGB, BGRA, etc.) |
 //! | [`PhotoDnaError`] | Comprehensive typed error handling |
 //! | [`HashOptions`] | Fine-grained control over hash computation |
+//! | [`MatchVerdict`] | Typed outcome of comparing two hashes |
+//! | [`HashStore`] | Collection of known hashes to match queries against |
+//! | [`ColumnarHashStore`] | Column-oriented hash collection for faster scans of rarely changed lists |
+//! | [`Region`] | Rectangular sub-region of an image (x, y, width, height) |
+//! | [`ImageRef`] | Borrowed pixel buffer with its dimensions, for bulk hashing |
 //!
 //! ## Features
 //!
@@ -26,6 +32,23 @@
 //! - **Typed Errors**: Every failure mode has a specific error variant
 //! - **Builder Pattern**: Ergonomic configuration via `GeneratorOptions` and `HashOptions`
 //! - **Test Utilities**: Mock hashes and fixtures for testing (via `test-utils` feature)
+//! - **Stub Generator**: SDK-free `Generator` returning fixture hashes (via `stub` feature)
+//! - **Image Decoding**: Hash `image::DynamicImage`s and image files (via `image` feature)
+//! - **Serde Support**: Serialize `Hash` and `HashStore` as hex or bytes, and load `HashOptions` from config files (via `serde` feature)
+//! - **ndarray Bridge**: Hash `(height, width, channels)` arrays (via `ndarray` feature)
+//! - **Memory-Mapped Stores**: Query multi-GB hash files in place (via `mmap` feature)
+//! - **Parquet Export**: Write labeled hashes for columnar analysis tools (via `arrow` feature)
+//! - **Verbose Capture**: Collect the library's stderr diagnostics per call (via `capture-verbose` feature, Unix only)
+//! - **Parallel Matching**: Batch `HashStore` queries across threads (via `rayon` feature)
+//! - **Unwind Containment**: Panics during library calls become errors (via `catch-ffi` feature)
+//!
+//! The `catch-ffi` feature wraps every hashing call in
+//! [`std::panic::catch_unwind`] and reports a caught unwind as
+//! [`PhotoDnaError::LibraryFailure`]. It **cannot** catch segfaults, aborts, or
+//! other faults inside the native library; only unwinds that reach the Rust
+//! side of the boundary are contained, which requires building with
+//! `-C panic=unwind` (the default). Unwinding out of the `extern "C"`
+//! function pointers themselves still aborts the process.
 //!
 //! ## Requirements
 //!
@@ -40,6 +63,11 @@
 //!
 //! See [`photodna-sys`](https://docs.rs/photodna-sys) for detailed SDK setup.
 //!
+//! Hashing needs the native library, available on Windows, Linux and macOS.
+//! The crate also builds on other platforms, where [`Generator::new`]
+//! returns [`PhotoDnaError::UnsupportedPlatform`]; see
+//! [Platform Support](Generator#platform-support).
+//!
 //! ## Quick Start
 //!
 //! ```rust,ignore
@@ -103,8 +131,10 @@
 //! - Create one `Generator` per thread (recommended), or
 //! - Wrap in `Arc<Mutex<Generator>>` for shared access
 //!
-//! The `max_threads` option controls the underlying library's thread pool.
-//! Operations exceeding this limit will block until a slot becomes available.
+//! The `max_threads` option limits how many hashing calls the underlying
+//! library runs at once. Calls exceeding this limit will block until a slot
+//! becomes available. Use [`GeneratorOptions::single_threaded`] to serialize
+//! all calls.
 //!
 //! ## Test Utilities
 //!
@@ -139,23 +169,74 @@
 #![deny(missing_docs)]
 #![deny(unsafe_op_in_unsafe_fn)]
 
+#[cfg(feature = "ndarray")]
+mod array;
+mod backend;
+#[cfg(feature = "image")]
+mod decode;
 mod error;
 mod hash;
+pub mod matching;
+mod rows;
+mod scratch;
+#[cfg(feature = "serde")]
+mod serialize;
+mod store;
+#[cfg(all(unix, feature = "capture-verbose"))]
+mod verbose;
 
 // Test utilities module (available with `test-utils` feature or in tests)
 #[cfg(any(test, feature = "test-utils"))]
 #[cfg_attr(docsrs, doc(cfg(feature = "test-utils")))]
 pub mod test_utils;
 
-pub use error::{PhotoDnaError, Result};
-pub use hash::{Hash, HASH_SIZE, HASH_SIZE_MAX};
+pub use error::{ErrorCategory, LibraryLoadReport, PhotoDnaError, Result};
+pub use hash::{
+    distance_bytes, ConfidenceCurve, Hash, HashEncoding, HashFormat, MatchVerdict, PreparedHash,
+    DEFAULT_MATCH_THRESHOLD, HASH_SIZE, HASH_SIZE_MAX, MAX_DISTANCE,
+};
+pub use rows::RowHasher;
+#[cfg(feature = "mmap")]
+pub use store::MmapHashStore;
+pub use store::{ColumnarHashStore, HashStore, StoreDiff};
 
+use backend::Backend;
 use photodna_sys::{self as sys, PhotoDnaOptions};
+use scratch::ScratchPool;
 use std::ffi::c_void;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::time::Duration;
 
 // Re-export commonly used constants from sys
 pub use photodna_sys::PHOTODNA_LIBRARY_VERSION as LIBRARY_VERSION;
 
+/// Smallest width and height, in pixels, the library will hash.
+pub const MIN_DIMENSION: u32 = 50;
+
+/// Default for [`GeneratorOptions::max_pixels`]: 100 million pixels, about
+/// a 10000x10000 image.
+pub const DEFAULT_MAX_PIXELS: u64 = 100_000_000;
+
+/// Returns `true` if an image of this size meets the library's minimum of
+/// [`MIN_DIMENSION`] pixels in each direction.
+///
+/// This is a cheap pre-check for filtering a corpus before hashing; the
+/// library remains the final authority and may still reject an image (for
+/// example as [`PhotoDnaError::ImageIsFlat`]).
+///
+/// # Examples
+///
+/// ```rust
+/// use photodna::is_hashable_size;
+///
+/// assert!(is_hashable_size(50, 50));
+/// assert!(!is_hashable_size(49, 1000));
+/// ```
+pub const fn is_hashable_size(width: u32, height: u32) -> bool {
+    width >= MIN_DIMENSION && height >= MIN_DIMENSION
+}
+
 /// Pixel format for raw image data.
 ///
 /// This specifies how color components are arranged in the pixel buffer.
@@ -169,7 +250,8 @@ pub enum PixelFormat {
 
     /// BGR format: 3 bytes per pixel (Blue, Green, Red).
     ///
-    /// Common in Windows BMP files and OpenCV.
+    /// Common in Windows BMP files and OpenCV. The library has no BGR
+    /// layout, so pixels are reordered to RGB before hashing.
     Bgr,
 
     /// RGBA format: 4 bytes per pixel (Red, Green, Blue, Alpha).
@@ -180,13 +262,16 @@ pub enum PixelFormat {
 
     /// BGRA format: 4 bytes per pixel (Blue, Green, Red, Alpha).
     ///
-    /// Common in Windows GDI and many image libraries.
+    /// Common in Windows GDI and many image libraries. Reordered to RGBA
+    /// before hashing.
     Bgra,
 
     /// ARGB format: 4 bytes per pixel (Alpha, Red, Green, Blue).
     Argb,
 
     /// ABGR format: 4 bytes per pixel (Alpha, Blue, Green, Red).
+    ///
+    /// Reordered to ARGB before hashing.
     Abgr,
 
     /// CMYK format: 4 bytes per pixel (Cyan, Magenta, Yellow, Key/Black).
@@ -195,7 +280,8 @@ pub enum PixelFormat {
     /// 8-bit grayscale: 1 byte per pixel.
     Gray8,
 
-    /// 32-bit grayscale: 4 bytes per pixel.
+    /// 32-bit grayscale: 4 bytes per pixel, in native byte order unless
+    /// set with [`HashOptions::gray32_endian`].
     Gray32,
 
     /// YCbCr color space: 3 bytes per pixel.
@@ -204,6 +290,8 @@ pub enum PixelFormat {
     /// YUV420P planar format.
     ///
     /// Y plane: 1 byte per pixel, U and V planes: 1 byte per 4 pixels.
+    /// Use [`Generator::compute_hash_yuv420p`] when the planes are separate
+    /// slices.
     Yuv420p,
 }
 
@@ -227,8 +315,166 @@ impl PixelFormat {
         }
     }
 
+    /// Returns the minimum buffer size in bytes for an image in this format.
+    ///
+    /// `stride` is the row stride in bytes, or 0 for tightly packed rows.
+    /// For [`Yuv420p`](Self::Yuv420p) the stride applies to the Y plane and
+    /// is followed by two chroma planes at half resolution (rounded up) in
+    /// each dimension.
+    ///
+    /// The computation saturates instead of overflowing, so absurdly large
+    /// dimensions yield `usize::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::PixelFormat;
+    ///
+    /// // CMYK is 4 bytes per pixel, YCbCr is 3
+    /// assert_eq!(PixelFormat::Cmyk.required_buffer_size(100, 50, 0), 20_000);
+    /// assert_eq!(PixelFormat::YCbCr.required_buffer_size(100, 50, 0), 15_000);
+    ///
+    /// // YUV420P: full Y plane plus quarter-size U and V planes
+    /// assert_eq!(PixelFormat::Yuv420p.required_buffer_size(100, 50, 0), 7_500);
+    ///
+    /// // An explicit stride overrides the packed row size
+    /// assert_eq!(PixelFormat::Rgb.required_buffer_size(100, 50, 320), 16_000);
+    /// ```
+    pub fn required_buffer_size(self, width: u32, height: u32, stride: u32) -> usize {
+        let (width, height, stride) = (width as usize, height as usize, stride as usize);
+
+        match self {
+            Self::Yuv420p => {
+                let y_stride = if stride == 0 { width } else { stride };
+                let chroma_stride = y_stride / 2 + y_stride % 2;
+                let chroma_rows = height / 2 + height % 2;
+                y_stride
+                    .saturating_mul(height)
+                    .saturating_add(chroma_stride.saturating_mul(chroma_rows).saturating_mul(2))
+            }
+            _ => {
+                let row = if stride == 0 {
+                    width.saturating_mul(self.bytes_per_pixel())
+                } else {
+                    stride
+                };
+                row.saturating_mul(height)
+            }
+        }
+    }
+
+    /// Checks that a buffer of `len` bytes can hold an image in this format.
+    ///
+    /// `stride` is the row stride in bytes, or 0 for tightly packed rows.
+    /// See [`required_buffer_size`](Self::required_buffer_size) for the
+    /// size computed for each format.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PhotoDnaError::BufferTooSmall`] carrying the size this
+    /// format requires if `len` is smaller.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::{PhotoDnaError, PixelFormat};
+    ///
+    /// // A 3-byte-per-pixel buffer is too small for a CMYK image
+    /// let ycbcr_sized = PixelFormat::YCbCr.required_buffer_size(64, 64, 0);
+    /// assert_eq!(
+    ///     PixelFormat::Cmyk.validate_buffer(ycbcr_sized, 64, 64, 0),
+    ///     Err(PhotoDnaError::BufferTooSmall { expected: 16_384, actual: 12_288 })
+    /// );
+    /// assert!(PixelFormat::YCbCr.validate_buffer(ycbcr_sized, 64, 64, 0).is_ok());
+    /// ```
+    pub fn validate_buffer(self, len: usize, width: u32, height: u32, stride: u32) -> Result<()> {
+        let expected = self.required_buffer_size(width, height, stride);
+        if len < expected {
+            return Err(PhotoDnaError::BufferTooSmall {
+                expected,
+                actual: len,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the channel order of packed RGB formats.
+    ///
+    /// Returns `None` for formats that are not packed RGB with an optional
+    /// alpha channel.
+    pub const fn channel_order(self) -> Option<ChannelOrder> {
+        match self {
+            Self::Rgb => Some(ChannelOrder::Rgb),
+            Self::Bgr => Some(ChannelOrder::Bgr),
+            Self::Rgba => Some(ChannelOrder::Rgba),
+            Self::Bgra => Some(ChannelOrder::Bgra),
+            Self::Argb => Some(ChannelOrder::Argb),
+            Self::Abgr => Some(ChannelOrder::Abgr),
+            _ => None,
+        }
+    }
+
+    /// Returns every pixel format, in declaration order.
+    pub const fn all() -> &'static [PixelFormat] {
+        &[
+            Self::Rgb,
+            Self::Bgr,
+            Self::Rgba,
+            Self::RgbaPremultiplied,
+            Self::Bgra,
+            Self::Argb,
+            Self::Abgr,
+            Self::Cmyk,
+            Self::Gray8,
+            Self::Gray32,
+            Self::YCbCr,
+            Self::Yuv420p,
+        ]
+    }
+
+    /// Returns the lowercase name of this format, e.g. `"rgba"`.
+    ///
+    /// This is the spelling accepted by [`from_name`](Self::from_name) and
+    /// [`FromStr`](std::str::FromStr).
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Rgb => "rgb",
+            Self::Bgr => "bgr",
+            Self::Rgba => "rgba",
+            Self::RgbaPremultiplied => "rgba_premultiplied",
+            Self::Bgra => "bgra",
+            Self::Argb => "argb",
+            Self::Abgr => "abgr",
+            Self::Cmyk => "cmyk",
+            Self::Gray8 => "gray8",
+            Self::Gray32 => "gray32",
+            Self::YCbCr => "ycbcr",
+            Self::Yuv420p => "yuv420p",
+        }
+    }
+
+    /// Looks up a pixel format by [`name`](Self::name), ignoring ASCII case.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::PixelFormat;
+    ///
+    /// assert_eq!(PixelFormat::from_name("RGBA"), Some(PixelFormat::Rgba));
+    /// assert_eq!(PixelFormat::from_name("rgb565"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|format| format.name().eq_ignore_ascii_case(name))
+    }
+
     /// Converts this pixel format to the PhotoDNA options flag.
-    fn to_options(self) -> PhotoDnaOptions {
+    ///
+    /// Formats the library cannot express share the flag of the layout
+    /// they are reordered to (see [`prepare_pixels`]).
+    const fn to_options(self) -> PhotoDnaOptions {
         match self {
             Self::Rgb | Self::Bgr => sys::PhotoDna_Rgb,
             Self::Rgba | Self::Bgra => sys::PhotoDna_Rgba,
@@ -241,6 +487,185 @@ impl PixelFormat {
             Self::Yuv420p => sys::PhotoDna_Yuv420p,
         }
     }
+
+    /// Returns the format whose layout flag is set in `options`.
+    ///
+    /// Reordered formats map to the layout the library receives.
+    #[cfg(feature = "stub")]
+    pub(crate) fn from_options(options: PhotoDnaOptions) -> Self {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|format| format.to_options() == options & sys::PhotoDna_PixelLayoutMask)
+            .unwrap_or(Self::Rgb)
+    }
+}
+
+/// Order of the color channels within each pixel of a packed RGB image.
+///
+/// Separates the order of the channels from the rest of the
+/// [`PixelFormat`]. The library only understands RGB, RGBA and ARGB, so
+/// the generator reorders BGR, BGRA and ABGR pixels into those layouts
+/// before hashing. Each order hashes the same image to the same result.
+///
+/// # Examples
+///
+/// ```rust
+/// use photodna::{ChannelOrder, HashOptions, PixelFormat};
+///
+/// assert_eq!(PixelFormat::from(ChannelOrder::Bgra), PixelFormat::Bgra);
+/// assert_eq!(ChannelOrder::Abgr.channels(), 4);
+///
+/// let options = HashOptions::new().input_channel_order(ChannelOrder::Bgr);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChannelOrder {
+    /// Red, green, blue.
+    Rgb,
+    /// Blue, green, red.
+    Bgr,
+    /// Red, green, blue, alpha.
+    Rgba,
+    /// Blue, green, red, alpha.
+    Bgra,
+    /// Alpha, red, green, blue.
+    Argb,
+    /// Alpha, blue, green, red.
+    Abgr,
+}
+
+impl ChannelOrder {
+    /// Returns the number of channels (bytes) per pixel.
+    pub const fn channels(self) -> usize {
+        match self {
+            Self::Rgb | Self::Bgr => 3,
+            Self::Rgba | Self::Bgra | Self::Argb | Self::Abgr => 4,
+        }
+    }
+
+    /// Returns, for each channel of the layout the library receives, the
+    /// index of that channel in this order. `None` if no reordering is
+    /// needed.
+    const fn swizzle(self) -> Option<&'static [usize]> {
+        match self {
+            Self::Rgb | Self::Rgba | Self::Argb => None,
+            Self::Bgr => Some(&[2, 1, 0]),
+            Self::Bgra => Some(&[2, 1, 0, 3]),
+            Self::Abgr => Some(&[0, 3, 2, 1]),
+        }
+    }
+}
+
+impl std::str::FromStr for PixelFormat {
+    type Err = PhotoDnaError;
+
+    /// Parses a format name with [`PixelFormat::from_name`], returning
+    /// [`PhotoDnaError::SourceFormatUnknown`] for unknown names.
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_name(s).ok_or(PhotoDnaError::SourceFormatUnknown)
+    }
+}
+
+impl From<ChannelOrder> for PixelFormat {
+    fn from(order: ChannelOrder) -> Self {
+        match order {
+            ChannelOrder::Rgb => Self::Rgb,
+            ChannelOrder::Bgr => Self::Bgr,
+            ChannelOrder::Rgba => Self::Rgba,
+            ChannelOrder::Bgra => Self::Bgra,
+            ChannelOrder::Argb => Self::Argb,
+            ChannelOrder::Abgr => Self::Abgr,
+        }
+    }
+}
+
+/// How the color channels of an image with alpha relate to its alpha.
+///
+/// Set with [`HashOptions::alpha_mode`] to describe the input when it does
+/// not match what its [`PixelFormat`] declares.
+///
+/// # Examples
+///
+/// ```rust
+/// use photodna::{AlphaMode, HashOptions, PixelFormat};
+///
+/// // A premultiplied BGRA buffer, e.g. from a compositor.
+/// let options = HashOptions::new()
+///     .pixel_format(PixelFormat::Bgra)
+///     .alpha_mode(AlphaMode::Premultiplied);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlphaMode {
+    /// Color channels are independent of alpha.
+    Straight,
+    /// Color channels have already been multiplied by alpha.
+    Premultiplied,
+}
+
+/// The byte order of multi-byte pixel values.
+///
+/// Set with [`HashOptions::gray32_endian`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endian {
+    /// Least significant byte first.
+    Little,
+    /// Most significant byte first.
+    Big,
+}
+
+impl Endian {
+    /// The byte order of the target platform, which the library reads.
+    pub const NATIVE: Self = if cfg!(target_endian = "big") {
+        Self::Big
+    } else {
+        Self::Little
+    };
+}
+
+/// How [`HashOptions::pad_to_minimum`] fills the added pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PadMode {
+    /// Repeat the nearest edge pixel.
+    Edge,
+    /// Mirror the image across its edges, without repeating the edge
+    /// pixel (`dcb|abcd|cba`).
+    Reflect,
+    /// Set every byte of the added pixels to this value.
+    Constant(u8),
+}
+
+impl PadMode {
+    /// Maps a padded coordinate to the source coordinate it copies, or
+    /// `None` for a constant pixel. `len` must be non-zero.
+    fn source(self, index: i64, len: u32) -> Option<usize> {
+        let len = i64::from(len);
+        if (0..len).contains(&index) {
+            return Some(index as usize);
+        }
+        match self {
+            Self::Edge => Some(index.clamp(0, len - 1) as usize),
+            Self::Reflect if len == 1 => Some(0),
+            Self::Reflect => {
+                let period = 2 * (len - 1);
+                let folded = index.rem_euclid(period);
+                Some(folded.min(period - folded) as usize)
+            }
+            Self::Constant(_) => None,
+        }
+    }
+}
+
+impl PixelFormat {
+    /// Returns the alpha position in the layout the library receives, and
+    /// how the library interprets it. `None` for formats without alpha.
+    const fn alpha(self) -> Option<(usize, AlphaMode)> {
+        match self {
+            Self::Rgba | Self::Bgra => Some((3, AlphaMode::Straight)),
+            Self::RgbaPremultiplied => Some((3, AlphaMode::Premultiplied)),
+            Self::Argb | Self::Abgr => Some((0, AlphaMode::Straight)),
+            _ => None,
+        }
+    }
 }
 
 /// Options for configuring the PhotoDNA generator.
@@ -256,13 +681,22 @@ impl PixelFormat {
 ///     .max_threads(4)
 ///     .library_dir("/custom/path/to/lib");
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GeneratorOptions {
     /// Maximum number of concurrent threads for hash computation.
     max_threads: i32,
 
     /// Custom path to the library directory.
     library_dir: Option<String>,
+
+    /// Number of initial hashing calls run with memory checking.
+    check_memory_calls: u32,
+
+    /// Number of idle conversion buffers kept for reuse.
+    scratch_pool_size: usize,
+
+    /// Largest `width * height` accepted for hashing.
+    max_pixels: u64,
 }
 
 impl Default for GeneratorOptions {
@@ -270,6 +704,9 @@ impl Default for GeneratorOptions {
         Self {
             max_threads: 4,
             library_dir: None,
+            check_memory_calls: 0,
+            scratch_pool_size: 0,
+            max_pixels: DEFAULT_MAX_PIXELS,
         }
     }
 }
@@ -293,6 +730,106 @@ impl GeneratorOptions {
         self
     }
 
+    /// Limits the generator to one hashing call at a time.
+    ///
+    /// Equivalent to `max_threads(1)`. Concurrent calls into the library
+    /// are serialized, which suits sandboxes that restrict parallelism.
+    ///
+    /// Note that `max_threads` is the library's limit on concurrent calls,
+    /// not a guarantee about threads it creates. The SDK does not document
+    /// whether initialization starts helper threads, so this crate cannot
+    /// promise that none are spawned; confirm with your platform's tools
+    /// (e.g. `/proc/<pid>/task` on Linux) if that matters.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::GeneratorOptions;
+    ///
+    /// let options = GeneratorOptions::new().single_threaded();
+    /// ```
+    pub fn single_threaded(self) -> Self {
+        self.max_threads(1)
+    }
+
+    /// Enables memory checking for the first `calls` hashing calls only.
+    ///
+    /// This is a debugging aid for tracking down a bad buffer: the first
+    /// `calls` calls made through the generator run as if
+    /// [`HashOptions::check_memory`] were set, and later calls run at full
+    /// speed. Calls that fail validation before reaching the library do not
+    /// count. Default is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::GeneratorOptions;
+    ///
+    /// let options = GeneratorOptions::new().check_memory_first_n(100);
+    /// ```
+    pub fn check_memory_first_n(mut self, calls: u32) -> Self {
+        self.check_memory_calls = calls;
+        self
+    }
+
+    /// Keeps up to `buffers` conversion buffers for reuse across calls.
+    ///
+    /// Formats the library cannot read directly (BGR-family orders and
+    /// separate YUV420P planes) are converted into a temporary buffer the
+    /// size of the image. By default each call allocates and frees its own.
+    /// With a pool, calls borrow an idle buffer and return it afterwards,
+    /// which removes that allocation once the pool is warm and eases
+    /// allocator contention when many calls run at once.
+    ///
+    /// The tradeoff is memory: each pooled buffer keeps the capacity of the
+    /// largest image it has converted, so the pool can hold up to `buffers`
+    /// times that size for the life of the generator. A size around
+    /// [`max_threads`](Self::max_threads) is usually enough. Default is 0
+    /// (no pool).
+    ///
+    /// Methods that take a [`HashScratch`] use that buffer instead of the
+    /// pool.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::GeneratorOptions;
+    ///
+    /// let options = GeneratorOptions::new().max_threads(8).scratch_pool_size(8);
+    /// ```
+    pub fn scratch_pool_size(mut self, buffers: usize) -> Self {
+        self.scratch_pool_size = buffers;
+        self
+    }
+
+    /// Rejects images with more than `pixels` pixels.
+    ///
+    /// Dimensions usually come from the image itself, and an untrusted
+    /// image can claim to be enormous. Conversion, padding and row
+    /// buffering allocate in proportion to the image, so every hashing
+    /// method checks `width * height` against this limit first and returns
+    /// [`PhotoDnaError::ImageTooLarge`] before allocating or calling the
+    /// library. Decoding methods check the dimensions in the file header
+    /// before decoding. Default is [`DEFAULT_MAX_PIXELS`]; `u64::MAX`
+    /// disables the check.
+    ///
+    /// The limit bounds the pixel count only. Callers that receive raw
+    /// buffers should still bound their length, since an image's buffer
+    /// can be several bytes per pixel.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::GeneratorOptions;
+    ///
+    /// // Nothing larger than 4K UHD.
+    /// let options = GeneratorOptions::new().max_pixels(3840 * 2160);
+    /// ```
+    pub fn max_pixels(mut self, pixels: u64) -> Self {
+        self.max_pixels = pixels;
+        self
+    }
+
     /// Sets a custom library directory path.
     ///
     /// By default, the library is loaded from the path configured
@@ -326,6 +863,22 @@ pub struct HashOptions {
 
     /// Enable memory checking (may impact performance).
     check_memory: bool,
+
+    /// Pass buffers to the library without checking their size.
+    skip_buffer_validation: bool,
+
+    /// How the input's color channels relate to alpha, if stated.
+    alpha_mode: Option<AlphaMode>,
+
+    /// Byte order of [`PixelFormat::Gray32`] input, if stated.
+    gray32_endian: Option<Endian>,
+
+    /// How to pad images smaller than [`MIN_DIMENSION`], if at all.
+    pad_to_minimum: Option<PadMode>,
+
+    /// Rotate and flip decoded files upright per their EXIF orientation.
+    #[cfg(feature = "image")]
+    apply_exif_orientation: bool,
 }
 
 impl HashOptions {
@@ -342,6 +895,112 @@ impl HashOptions {
         self
     }
 
+    /// Sets the input as packed RGB pixels with the given channel order.
+    ///
+    /// Equivalent to [`pixel_format`](Self::pixel_format) with the matching
+    /// format; whichever of the two is called last wins. Orders the library
+    /// cannot read directly are reordered before hashing.
+    pub fn input_channel_order(self, order: ChannelOrder) -> Self {
+        self.pixel_format(order.into())
+    }
+
+    /// States whether the input's color channels are premultiplied by
+    /// alpha.
+    ///
+    /// The library reads [`PixelFormat::RgbaPremultiplied`] as
+    /// premultiplied and every other format with alpha as straight. When
+    /// `mode` differs from that, the pixels are converted in a scratch
+    /// buffer before hashing: premultiplied input is unpremultiplied for
+    /// straight formats, and straight input is premultiplied for
+    /// `RgbaPremultiplied`. Conversions round to the nearest value, and
+    /// unpremultiplying fully transparent pixels yields black.
+    ///
+    /// Ignored for formats without alpha. By default the input is assumed
+    /// to match its format.
+    pub fn alpha_mode(mut self, mode: AlphaMode) -> Self {
+        self.alpha_mode = Some(mode);
+        self
+    }
+
+    /// Sets the byte order of [`PixelFormat::Gray32`] pixel values.
+    ///
+    /// The library reads each 32-bit value in the platform's byte order
+    /// ([`Endian::NATIVE`]), so the same bytes hash differently on little-
+    /// and big-endian machines. Set this when the buffer's byte order is
+    /// known, e.g. when it was read from a file: if it differs from the
+    /// platform's, each value is byteswapped in a scratch buffer before
+    /// hashing. Ignored for other formats. Defaults to native.
+    pub fn gray32_endian(mut self, endian: Endian) -> Self {
+        self.gray32_endian = Some(endian);
+        self
+    }
+
+    /// Pads images smaller than [`MIN_DIMENSION`] up to it instead of
+    /// rejecting them.
+    ///
+    /// When the image is narrower or shorter than the minimum, it is
+    /// centered in a copy exactly [`MIN_DIMENSION`] pixels across in that
+    /// direction, and the added pixels are filled according to `mode`.
+    /// Dimensions already large enough are left alone, so a 40×60 image
+    /// is hashed as 50×60.
+    ///
+    /// This is an opt-in for pipelines that would rather hash everything
+    /// than drop icons and thumbnails. A padded hash describes the padded
+    /// image: it differs from the hash of the same picture at its native
+    /// size, and is unlikely to match a full-size copy of it.
+    ///
+    /// Applies when hashing whole images with
+    /// [`Generator::compute_hash`] and its variants, and
+    /// [`Generator::compute_hash_with_border_detection`], whose content
+    /// region is then reported in padded coordinates. Sub-regions and
+    /// tiles must still meet the minimum, and [`PixelFormat::Yuv420p`]
+    /// images are never padded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::{HashOptions, PadMode};
+    ///
+    /// let options = HashOptions::new().pad_to_minimum(PadMode::Constant(255));
+    /// assert!(options.can_hash_dimensions(32, 32));
+    /// ```
+    pub fn pad_to_minimum(mut self, mode: PadMode) -> Self {
+        self.pad_to_minimum = Some(mode);
+        self
+    }
+
+    /// Returns the padding to apply to whole images, if any.
+    fn padding(&self) -> Option<PadMode> {
+        self.pad_to_minimum
+            .filter(|_| self.pixel_format != PixelFormat::Yuv420p)
+    }
+
+    /// Returns the padding to apply to a `width` × `height` image, if any.
+    fn padding_for(&self, width: u32, height: u32) -> Option<PadMode> {
+        self.padding().filter(|_| !is_hashable_size(width, height))
+    }
+
+    /// Rotates and flips decoded image files upright according to their
+    /// EXIF orientation tag before hashing.
+    ///
+    /// Phones usually store photos as captured and record how to display
+    /// them in the orientation tag, while many editors and viewers save an
+    /// already rotated copy. Applying the tag makes both hash the same
+    /// upright image. This is input normalization and is independent of
+    /// the library's own rotate/flip matching, which
+    /// [`no_rotate_flip`](Self::no_rotate_flip) controls.
+    ///
+    /// Only applies where this crate decodes the file itself:
+    /// `Generator::compute_hash_from_path` and `Generator::hash_paths`. The
+    /// tag is read from JPEG and PNG files; files without one are hashed
+    /// as decoded. Default is `false`.
+    #[cfg(feature = "image")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "image")))]
+    pub fn apply_exif_orientation(mut self, enable: bool) -> Self {
+        self.apply_exif_orientation = enable;
+        self
+    }
+
     /// Enables border detection and removal.
     ///
     /// When enabled, the library will attempt to detect and remove
@@ -362,7 +1021,8 @@ impl HashOptions {
 
     /// Enables verbose debug output.
     ///
-    /// Debug messages will be written to stderr.
+    /// Debug messages will be written to stderr. To collect them instead,
+    /// see `Generator::compute_hash_verbose` (`capture-verbose` feature).
     pub fn verbose(mut self, enable: bool) -> Self {
         self.verbose = enable;
         self
@@ -377,8 +1037,87 @@ impl HashOptions {
         self
     }
 
+    /// Skips the crate's check that image buffers are large enough.
+    ///
+    /// Every hashing call normally compares the buffer length against
+    /// [`PixelFormat::required_buffer_size`] and returns
+    /// [`PhotoDnaError::BufferTooSmall`] before reaching the library. With
+    /// this set, the buffer pointer is passed straight through and only the
+    /// library's own checks remain, which do not include the buffer length.
+    /// The check is a few arithmetic operations, so this only matters on
+    /// hot paths that have already validated their buffers. Default is
+    /// `false`.
+    ///
+    /// # Safety
+    ///
+    /// Every image hashed with these options must be at least
+    /// [`PixelFormat::required_buffer_size`] bytes for its format,
+    /// dimensions and stride. A shorter buffer lets the library read out of
+    /// bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::HashOptions;
+    ///
+    /// // SAFETY: every buffer hashed with `options` is checked by the caller.
+    /// let options = unsafe { HashOptions::new().skip_buffer_validation(true) };
+    /// ```
+    pub unsafe fn skip_buffer_validation(mut self, skip: bool) -> Self {
+        self.skip_buffer_validation = skip;
+        self
+    }
+
+    /// Returns `true` if an image of this size passes the size checks that
+    /// can be made before hashing.
+    ///
+    /// This is [`is_hashable_size`], or any non-zero size when
+    /// [`pad_to_minimum`](Self::pad_to_minimum) applies. With
+    /// [`remove_border`](Self::remove_border) enabled, the content left
+    /// after border removal must also be at least [`MIN_DIMENSION`] pixels
+    /// in each direction; that depends on the detected border, so it can
+    /// only fail at hashing time with
+    /// [`PhotoDnaError::NoBorderImageTooSmall`].
+    pub fn can_hash_dimensions(&self, width: u32, height: u32) -> bool {
+        if self.padding().is_some() {
+            width > 0 && height > 0
+        } else {
+            is_hashable_size(width, height)
+        }
+    }
+
+    /// Checks that a `len`-byte buffer holds the image, unless
+    /// [`skip_buffer_validation`](Self::skip_buffer_validation) is set.
+    fn validate_buffer(&self, len: usize, width: u32, height: u32, stride: u32) -> Result<()> {
+        if self.skip_buffer_validation {
+            return Ok(());
+        }
+        self.pixel_format
+            .validate_buffer(len, width, height, stride)
+    }
+
+    /// Returns the number of hash bytes the library writes with these
+    /// options.
+    ///
+    /// The safe layer always requests binary Edge V2 hashes, so this is
+    /// [`HASH_SIZE`]; it is computed from the library flags with
+    /// [`photodna_sys::hash_size_for_options`] so buffer sizes can be
+    /// worked out without reaching into `photodna_sys`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::{HashOptions, PixelFormat, HASH_SIZE};
+    ///
+    /// let options = HashOptions::new().pixel_format(PixelFormat::Rgba);
+    /// assert_eq!(options.output_size(), HASH_SIZE);
+    /// ```
+    pub const fn output_size(&self) -> usize {
+        sys::hash_size_for_options(self.to_sys_options())
+    }
+
     /// Converts these options to PhotoDNA library flags.
-    fn to_sys_options(self) -> PhotoDnaOptions {
+    const fn to_sys_options(self) -> PhotoDnaOptions {
         let mut opts = sys::PhotoDna_HashFormatEdgeV2;
         opts |= self.pixel_format.to_options();
 
@@ -399,36 +1138,340 @@ impl HashOptions {
     }
 }
 
-/// The result of a hash computation with border detection.
+/// A rectangular area within an image, in pixels.
 ///
-/// Contains the primary hash and optionally a secondary hash
-/// computed from the image with borders removed.
-#[derive(Debug, Clone)]
-pub struct BorderHashResult {
-    /// The hash of the original image.
-    pub primary: Hash,
-
-    /// The hash with borders removed, if a border was detected.
-    pub borderless: Option<Hash>,
-
-    /// The detected border region (x, y, width, height).
-    ///
-    /// This describes the content area after border removal.
-    pub content_region: Option<(i32, i32, i32, i32)>,
-}
-
-/// The PhotoDNA hash generator.
+/// Regions are always described by their top-left corner and size, which is
+/// the layout PhotoDNA expects. Use [`from_corners`](Self::from_corners) when
+/// you have two opposite corners instead.
 ///
-/// This struct manages the underlying PhotoDNA library instance and provides
-/// safe methods for computing perceptual hashes.
+/// # Examples
 ///
-/// # Thread Safety
+/// ```rust
+/// use photodna::Region;
 ///
-/// The generator is [`Send`] but not [`Sync`]. To use from multiple threads,
-/// either:
+/// let a = Region::from_xywh(10, 20, 100, 50);
+/// let b = Region::from_corners(10, 20, 110, 70);
+/// assert_eq!(a, b);
+///
+/// // Tuples are interpreted as (x, y, width, height)
+/// assert_eq!(Region::from((10, 20, 100, 50)), a);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Region {
+    /// Left edge (X) of the region.
+    pub x: u32,
+    /// Top edge (Y) of the region.
+    pub y: u32,
+    /// Width of the region in pixels.
+    pub width: u32,
+    /// Height of the region in pixels.
+    pub height: u32,
+}
+
+impl Region {
+    /// Creates a region from its top-left corner and size.
+    #[inline]
+    pub const fn from_xywh(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Creates a region from two opposite corners.
+    ///
+    /// The corners may be given in any order. The second corner is
+    /// exclusive, so `from_corners(0, 0, 50, 50)` is a 50×50 region.
+    pub fn from_corners(x1: u32, y1: u32, x2: u32, y2: u32) -> Self {
+        Self {
+            x: x1.min(x2),
+            y: y1.min(y2),
+            width: x1.abs_diff(x2),
+            height: y1.abs_diff(y2),
+        }
+    }
+
+    /// Returns `true` if the region lies entirely within an image of the
+    /// given dimensions.
+    pub fn fits_within(&self, width: u32, height: u32) -> bool {
+        match (
+            self.x.checked_add(self.width),
+            self.y.checked_add(self.height),
+        ) {
+            (Some(right), Some(bottom)) => right <= width && bottom <= height,
+            _ => false,
+        }
+    }
+
+    /// Splits a `width` × `height` image into a `columns` × `rows` grid of
+    /// tiles, in row-major order.
+    ///
+    /// Tiles in the last column and row absorb any remainder, so the grid
+    /// always covers the whole image. Returns no tiles if `columns` or
+    /// `rows` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::Region;
+    ///
+    /// let tiles = Region::grid(250, 100, 2, 1);
+    /// assert_eq!(tiles, [Region::from_xywh(0, 0, 125, 100), Region::from_xywh(125, 0, 125, 100)]);
+    /// ```
+    pub fn grid(width: u32, height: u32, columns: u32, rows: u32) -> Vec<Self> {
+        if columns == 0 || rows == 0 {
+            return Vec::new();
+        }
+
+        let (tile_w, tile_h) = (width / columns, height / rows);
+        let mut tiles = Vec::with_capacity(columns as usize * rows as usize);
+        for row in 0..rows {
+            for column in 0..columns {
+                let (x, y) = (column * tile_w, row * tile_h);
+                let w = if column + 1 == columns {
+                    width - x
+                } else {
+                    tile_w
+                };
+                let h = if row + 1 == rows { height - y } else { tile_h };
+                tiles.push(Self::from_xywh(x, y, w, h));
+            }
+        }
+        tiles
+    }
+
+    /// Checks that the region is non-empty and lies within an image of the
+    /// given dimensions.
+    fn check_within(&self, width: u32, height: u32) -> Result<()> {
+        if !self.fits_within(width, height) {
+            return Err(PhotoDnaError::InvalidSubImage);
+        }
+        if self.width == 0 || self.height == 0 {
+            return Err(PhotoDnaError::InvalidDimensions {
+                width: self.width,
+                height: self.height,
+            });
+        }
+        Ok(())
+    }
+
+    /// Converts the content area reported by the library, if it is valid.
+    fn from_hash_result(result: &sys::HashResult) -> Option<Self> {
+        // Copy packed fields to avoid unaligned access
+        let (x, y, w, h) = (
+            result.header_dimensions_image_x,
+            result.header_dimensions_image_y,
+            result.header_dimensions_image_w,
+            result.header_dimensions_image_h,
+        );
+
+        Some(Self::from_xywh(
+            u32::try_from(x).ok()?,
+            u32::try_from(y).ok()?,
+            u32::try_from(w).ok()?,
+            u32::try_from(h).ok()?,
+        ))
+    }
+}
+
+impl From<(u32, u32, u32, u32)> for Region {
+    /// Interprets the tuple as `(x, y, width, height)`.
+    fn from((x, y, width, height): (u32, u32, u32, u32)) -> Self {
+        Self::from_xywh(x, y, width, height)
+    }
+}
+
+/// A borrowed image buffer together with its dimensions.
+///
+/// Used by the bulk hashing methods, which take many images at once.
+///
+/// # Examples
+///
+/// ```rust
+/// use photodna::ImageRef;
+///
+/// let pixels = vec![0u8; 64 * 64 * 3];
+/// let image = ImageRef::new(&pixels, 64, 64);
+/// assert_eq!(image.stride, 0);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ImageRef<'a> {
+    /// Raw pixel data.
+    pub data: &'a [u8],
+    /// Image width in pixels.
+    pub width: u32,
+    /// Image height in pixels.
+    pub height: u32,
+    /// Row stride in bytes, or 0 to auto-calculate.
+    pub stride: u32,
+}
+
+impl<'a> ImageRef<'a> {
+    /// Creates an image reference with an auto-calculated stride.
+    #[inline]
+    pub const fn new(data: &'a [u8], width: u32, height: u32) -> Self {
+        Self {
+            data,
+            width,
+            height,
+            stride: 0,
+        }
+    }
+
+    /// Sets an explicit row stride in bytes.
+    #[inline]
+    pub const fn with_stride(mut self, stride: u32) -> Self {
+        self.stride = stride;
+        self
+    }
+}
+
+/// A reusable conversion buffer for allocation-free hashing.
+///
+/// Formats the library cannot read directly (BGR-family layouts and split
+/// YUV420P planes) are converted into a temporary buffer before hashing.
+/// Passing the same scratch to
+/// [`Generator::compute_hash_with_scratch`] or
+/// [`Generator::compute_hash_yuv420p_with_scratch`] reuses that buffer
+/// across calls.
+///
+/// The buffer grows to the largest converted image it has seen and never
+/// shrinks on its own; call [`shrink_to_fit`](Self::shrink_to_fit) to
+/// release it. Formats that need no conversion leave it untouched.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let mut scratch = HashScratch::new();
+/// for frame in frames {
+///     let hash = generator.compute_hash_with_scratch(
+///         frame.data(), frame.width(), frame.height(), 0,
+///         HashOptions::new().pixel_format(PixelFormat::Bgra),
+///         &mut scratch,
+///     )?;
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HashScratch {
+    buffer: Vec<u8>,
+}
+
+impl HashScratch {
+    /// Creates an empty scratch buffer.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { buffer: Vec::new() }
+    }
+
+    /// Creates a scratch buffer with room for `bytes` bytes of converted
+    /// pixel data.
+    #[inline]
+    pub fn with_capacity(bytes: usize) -> Self {
+        Self {
+            buffer: Vec::with_capacity(bytes),
+        }
+    }
+
+    /// Returns the number of bytes the buffer can hold without reallocating.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// Releases the buffer's memory.
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        self.buffer = Vec::new();
+    }
+}
+
+/// The result of a hash computation with border detection.
+///
+/// Contains the primary hash and optionally a secondary hash
+/// computed from the image with borders removed.
+#[derive(Debug, Clone)]
+pub struct BorderHashResult {
+    /// The hash of the original image.
+    pub primary: Hash,
+
+    /// The hash with borders removed, if a border was detected.
+    pub borderless: Option<Hash>,
+
+    /// The detected content region.
+    ///
+    /// This describes the content area after border removal, in the
+    /// coordinates of the input image. It is the only geometry the library
+    /// reports: the resolution it resizes images to internally is neither
+    /// documented nor returned, so it cannot be surfaced here.
+    pub content_region: Option<Region>,
+}
+
+/// The library version decoded from its packed integer form.
+///
+/// The packed value returned by [`Generator::library_version`] holds the
+/// major version in the high 16 bits and the minor version in the low 16
+/// bits. Displays as `major.minor`.
+///
+/// # Examples
+///
+/// ```rust
+/// use photodna::LibraryVersion;
+///
+/// let version = LibraryVersion::from((1 << 16) | 5);
+/// assert_eq!(version, LibraryVersion { major: 1, minor: 5 });
+/// assert_eq!(version.to_string(), "1.5");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct LibraryVersion {
+    /// Major version number.
+    pub major: u16,
+    /// Minor version number.
+    pub minor: u16,
+}
+
+impl From<i32> for LibraryVersion {
+    fn from(packed: i32) -> Self {
+        let packed = packed as u32;
+        Self {
+            major: (packed >> 16) as u16,
+            minor: packed as u16,
+        }
+    }
+}
+
+impl std::fmt::Display for LibraryVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// The PhotoDNA hash generator.
+///
+/// This struct manages the underlying PhotoDNA library instance and provides
+/// safe methods for computing perceptual hashes.
+///
+/// # Thread Safety
+///
+/// The generator is [`Send`] but not [`Sync`]. To use from multiple threads,
+/// either:
 /// - Create one `Generator` per thread, or
 /// - Wrap in `Arc<Mutex<Generator>>` for shared access
 ///
+/// # Platform Support
+///
+/// The type exists on every platform, so code using it compiles
+/// everywhere, but only some targets can create a working generator:
+///
+/// | Target | [`Generator::new`] | `Generator::new_stub` (`stub` feature) |
+/// |--------|--------------------|----------------------------------------|
+/// | Windows, Linux, macOS | Loads the native SDK library | Fixture hashes |
+/// | Anything else, including the BSDs | [`PhotoDnaError::UnsupportedPlatform`] | Fixture hashes |
+///
+/// The WebAssembly build of the library is exposed by `photodna-sys` (its
+/// `wasm` feature) as raw module bytes only; this crate does not run it.
+///
 /// # Examples
 ///
 /// ```rust,ignore
@@ -445,13 +1488,23 @@ pub struct BorderHashResult {
 /// // Compute a hash
 /// let hash = generator.compute_hash_rgb(&image_data, 640, 480)?;
 /// ```
-#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
 pub struct Generator {
-    /// The underlying sys-level generator.
-    inner: sys::EdgeHashGenerator,
+    /// The library implementation hashes are computed with.
+    inner: Box<dyn Backend>,
+    /// Remaining calls to run with memory checking forced on.
+    check_memory_calls: AtomicU32,
+    /// Conversion buffers shared by calls without their own scratch.
+    scratch: ScratchPool,
+    /// Largest `width * height` accepted for hashing.
+    max_pixels: u64,
+    /// Creates a replacement backend for [`reload`](Self::reload), if the
+    /// backend can be reloaded.
+    loader: Option<Loader>,
 }
 
-#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+/// Creates a fresh backend with the options a generator was built with.
+type Loader = Box<dyn Fn() -> Result<Box<dyn Backend>> + Send>;
+
 impl Generator {
     /// Creates a new PhotoDNA generator with the given options.
     ///
@@ -463,7 +1516,12 @@ impl Generator {
     ///
     /// # Errors
     ///
-    /// Returns an error if the library cannot be loaded or initialized.
+    /// Returns [`PhotoDnaError::InitializationFailed`] if the library cannot
+    /// be loaded or initialized. Its [`LibraryLoadReport`] lists every
+    /// library path that was tried.
+    ///
+    /// Returns [`PhotoDnaError::UnsupportedPlatform`] on platforms without a
+    /// native library; see [Platform Support](Generator#platform-support).
     ///
     /// # Examples
     ///
@@ -472,33 +1530,342 @@ impl Generator {
     ///
     /// let generator = Generator::new(GeneratorOptions::default())?;
     /// ```
+    #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+    pub fn new(options: GeneratorOptions) -> Result<Self> {
+        let library_dir = options.library_dir.clone();
+        let max_threads = options.max_threads;
+        let loader: Loader = Box::new(move || {
+            let inner = sys::EdgeHashGenerator::load(library_dir.as_deref(), max_threads)
+                .map_err(|report| PhotoDnaError::InitializationFailed(report.into()))?;
+            Ok(Box::new(inner) as Box<dyn Backend>)
+        });
+
+        Ok(Self {
+            inner: loader()?,
+            check_memory_calls: AtomicU32::new(options.check_memory_calls),
+            scratch: ScratchPool::new(options.scratch_pool_size),
+            max_pixels: options.max_pixels,
+            loader: Some(loader),
+        })
+    }
+
+    /// Creates a new PhotoDNA generator with the given options.
+    ///
+    /// # Errors
+    ///
+    /// Always returns [`PhotoDnaError::UnsupportedPlatform`]: no PhotoDNA
+    /// library is available for this platform. See
+    /// [Platform Support](Generator#platform-support).
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
     pub fn new(options: GeneratorOptions) -> Result<Self> {
-        let inner =
-            sys::EdgeHashGenerator::new(options.library_dir.as_deref(), options.max_threads)
-                .map_err(PhotoDnaError::InitializationFailed)?;
+        let _ = options;
+        Err(PhotoDnaError::UnsupportedPlatform)
+    }
+
+    /// Creates a generator and checks that the library hashes a built-in
+    /// reference image sensibly.
+    ///
+    /// Use this as a start-up health check in deployments. See
+    /// [`verify`](Self::verify) for what is checked.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from [`new`](Self::new), or
+    /// [`PhotoDnaError::VerificationFailed`] if the check fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use photodna::{Generator, GeneratorOptions};
+    ///
+    /// let generator = Generator::new_verified(GeneratorOptions::default())?;
+    /// ```
+    pub fn new_verified(options: GeneratorOptions) -> Result<Self> {
+        let generator = Self::new(options)?;
+        generator.verify()?;
+        Ok(generator)
+    }
+
+    /// Hashes a built-in 64×64 reference image and checks the result.
+    ///
+    /// The check passes when the image hashes without error to a full,
+    /// non-zero [`HASH_SIZE`]-byte hash, and hashing it again gives the
+    /// same bytes. This catches a library that fails to hash, returns
+    /// garbage, or is nondeterministic.
+    ///
+    /// When an expected hash has been recorded for the loaded library
+    /// version, the hash must also match it exactly. Golden values can only
+    /// be produced by the licensed SDK, so none is recorded yet and this
+    /// comparison is skipped; see `test_utils::CALIBRATION_EXPECTED_HASH`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PhotoDnaError::VerificationFailed`] describing the first
+    /// check that failed.
+    pub fn verify(&self) -> Result<()> {
+        self.verify_against(REFERENCE_LIBRARY_VERSION.zip(REFERENCE_EXPECTED_HASH))
+    }
+
+    /// [`verify`](Self::verify), comparing against `expected` (a library
+    /// version and the reference hash recorded with it) when given.
+    fn verify_against(&self, expected: Option<(&str, [u8; HASH_SIZE])>) -> Result<()> {
+        let failed = PhotoDnaError::VerificationFailed;
+        let (image, side) = reference_image();
+
+        let hash = |attempt| {
+            self.compute_hash_rgb(&image, side, side)
+                .map_err(|e| failed(format!("reference image ({} attempt): {}", attempt, e)))
+        };
+        let first = hash("first")?;
+        let second = hash("second")?;
 
-        Ok(Self { inner })
+        if !first.is_full() {
+            return Err(failed(format!(
+                "reference hash has {} bytes, expected {}",
+                first.len(),
+                HASH_SIZE
+            )));
+        }
+        if first.is_empty() {
+            return Err(failed("reference hash is all zeros".to_string()));
+        }
+        if first != second {
+            return Err(failed(
+                "reference image hashed differently twice".to_string(),
+            ));
+        }
+        if let Some((version, hash)) = expected {
+            if self.library_version_text() == Some(version) && first.as_bytes() != &hash[..] {
+                return Err(failed(format!(
+                    "reference hash differs from the one recorded with library version {}",
+                    version
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates a stub generator that does **not** compute PhotoDNA hashes.
+    ///
+    /// Every successful `compute_hash*` call returns the same deterministic
+    /// fixture hash from [`test_utils::fixtures`]. Input validation (buffer
+    /// sizes, dimensions, regions) still runs, so downstream crates can
+    /// exercise their integration with the full `Generator` API in CI
+    /// environments where the proprietary SDK is unavailable.
+    ///
+    /// Never use a stub generator in production: its output has no relation
+    /// to the image content.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::Generator;
+    ///
+    /// let generator = Generator::new_stub();
+    /// let image = vec![0u8; 64 * 64 * 3];
+    /// let hash = generator.compute_hash_rgb(&image, 64, 64).unwrap();
+    /// assert_eq!(hash, photodna::test_utils::fixtures::sample_hash_a());
+    /// ```
+    #[cfg(feature = "stub")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stub")))]
+    pub fn new_stub() -> Self {
+        Self::from_backend(backend::StubBackend::new())
+    }
+
+    /// Creates a stub generator whose hashes are derived from the pixels.
+    ///
+    /// Like [`new_stub`](Self::new_stub), this does **not** compute
+    /// PhotoDNA hashes, but each hash is a deterministic function of the
+    /// image's dimensions and pixel values (padding bytes between rows are
+    /// ignored). Identical images always get identical hashes, on every
+    /// platform and run, and different images get unrelated ones. This
+    /// makes the stub suitable for testing deduplication end to end
+    /// without the SDK: exact duplicates match, but resized, re-encoded or
+    /// otherwise near-duplicate images do not, unlike with real PhotoDNA.
+    ///
+    /// Never use a stub generator in production.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use photodna::Generator;
+    ///
+    /// let generator = Generator::new_content_stub();
+    /// let (a, b) = (vec![0u8; 64 * 64 * 3], vec![1u8; 64 * 64 * 3]);
+    /// let hash_a = generator.compute_hash_rgb(&a, 64, 64).unwrap();
+    /// assert_eq!(generator.compute_hash_rgb(&a, 64, 64).unwrap(), hash_a);
+    /// assert_ne!(generator.compute_hash_rgb(&b, 64, 64).unwrap(), hash_a);
+    /// ```
+    #[cfg(feature = "stub")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "stub")))]
+    pub fn new_content_stub() -> Self {
+        Self::from_backend(backend::StubBackend::content())
+    }
+
+    /// Creates a generator backed by an arbitrary backend implementation.
+    #[cfg(any(test, feature = "stub"))]
+    pub(crate) fn from_backend(backend: impl Backend + Send + 'static) -> Self {
+        Self {
+            inner: Box::new(backend),
+            check_memory_calls: AtomicU32::new(0),
+            scratch: ScratchPool::default(),
+            max_pixels: DEFAULT_MAX_PIXELS,
+            loader: None,
+        }
+    }
+
+    /// Makes [`reload`](Self::reload) replace the backend with `loader`'s.
+    #[cfg(test)]
+    pub(crate) fn with_loader(
+        mut self,
+        loader: impl Fn() -> Result<Box<dyn Backend>> + Send + 'static,
+    ) -> Self {
+        self.loader = Some(Box::new(loader));
+        self
+    }
+
+    /// Replaces the PhotoDNA library instance with a freshly initialized
+    /// one.
+    ///
+    /// The instance is created from the same directory and with the same
+    /// `max_threads` as in [`new`](Self::new). Use this to recover from a
+    /// library instance that keeps failing with
+    /// [`PhotoDnaError::LibraryFailure`]; see also
+    /// [`compute_hash_resilient`](Self::compute_hash_resilient).
+    ///
+    /// The new instance is initialized before the old one is released, so
+    /// if that fails the generator keeps working with the old instance.
+    /// Because the library is still loaded at that point, the operating
+    /// system hands back the same loaded image: this re-initializes the
+    /// library's instance state, it does not unload the library or pick up
+    /// a changed file on disk. The remaining memory-check calls, the
+    /// scratch pool and the pixel limit are unaffected.
+    ///
+    /// Generators created without loading a library, such as stubs, have
+    /// nothing to reload and keep their backend.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PhotoDnaError::InitializationFailed`] if the library can no
+    /// longer be loaded.
+    pub fn reload(&mut self) -> Result<()> {
+        if let Some(loader) = &self.loader {
+            self.inner = loader()?;
+        }
+        Ok(())
     }
 
     /// Returns the last error number from the library.
     ///
     /// This can be useful for debugging after a failed operation.
     pub fn last_error_code(&self) -> i32 {
-        self.inner.get_error_number()
+        self.inner.error_number()
+    }
+
+    /// Returns the library's last error as a [`PhotoDnaError`].
+    ///
+    /// This is [`last_error_code`](Self::last_error_code) converted with
+    /// [`PhotoDnaError::from_error_code`], or `None` if the error number is
+    /// 0 (no error).
+    pub fn last_error(&self) -> Option<PhotoDnaError> {
+        match self.last_error_code() {
+            0 => None,
+            code => Some(PhotoDnaError::from_error_code(code)),
+        }
+    }
+
+    /// Returns the number of hashing calls currently inside the library.
+    ///
+    /// A generator is not [`Sync`], so it runs at most one call at a time;
+    /// the count therefore spans every generator in the process, such as
+    /// one per worker thread. Export it to see how close a service is to
+    /// its thread budget. The value is read with a relaxed load and may be
+    /// stale by the time it is used.
+    pub fn inflight(&self) -> usize {
+        INFLIGHT.load(Ordering::Relaxed)
     }
 
     /// Returns a human-readable description for an error code.
     pub fn error_description(&self, code: i32) -> Option<&str> {
-        self.inner.get_error_string(code)
+        self.inner.error_string(code)
+    }
+
+    /// Checks that `hash` is structurally a valid Edge V2 hash.
+    ///
+    /// Use this to reject corrupt hashes received from elsewhere before
+    /// storing them. The library has no validation entry point, and Edge V2
+    /// hashes carry no documented header or checksum, so this checks the
+    /// structure the library does define: the hash must be exactly
+    /// [`HASH_SIZE`] bytes of binary data, not undecoded Base64 text (see
+    /// [`Hash::format`]). A hash that passes can still be one no image
+    /// produces.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PhotoDnaError::InvalidHash`] if the hash is partial or
+    /// holds Base64 text.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use photodna::{Generator, GeneratorOptions, Hash};
+    ///
+    /// let generator = Generator::new(GeneratorOptions::default())?;
+    /// let received = Hash::from_slice(&[1, 2, 3]).unwrap();
+    /// assert!(generator.validate_hash(&received).is_err());
+    /// # Ok::<(), photodna::PhotoDnaError>(())
+    /// ```
+    pub fn validate_hash(&self, hash: &Hash) -> Result<()> {
+        if hash.is_full() && hash.format() == HashFormat::Binary {
+            Ok(())
+        } else {
+            Err(PhotoDnaError::InvalidHash)
+        }
     }
 
     /// Returns the library version as a packed integer.
     ///
     /// High 16 bits = major version, low 16 bits = minor version.
+    ///
+    /// This is a legacy accessor: decoding it by hand is error-prone and it
+    /// carries no patch number. Prefer [`version`](Self::version) or
+    /// [`version_tuple`](Self::version_tuple).
     pub fn library_version(&self) -> i32 {
         self.inner.library_version()
     }
 
+    /// Returns the library version decoded from
+    /// [`library_version`](Self::library_version).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// println!("PhotoDNA {}", generator.version());
+    /// ```
+    pub fn version(&self) -> LibraryVersion {
+        LibraryVersion::from(self.inner.library_version())
+    }
+
+    /// Returns the library version as `(major, minor, patch)`.
+    ///
+    /// Each component comes from its dedicated library call, so no bit
+    /// unpacking is involved. Out-of-range values are clamped to `u16`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let (major, minor, patch) = generator.version_tuple();
+    /// println!("PhotoDNA {}.{}.{}", major, minor, patch);
+    /// ```
+    pub fn version_tuple(&self) -> (u16, u16, u16) {
+        let component = |value: i32| value.clamp(0, i32::from(u16::MAX)) as u16;
+        (
+            component(self.inner.library_version_major()),
+            component(self.inner.library_version_minor()),
+            component(self.inner.library_version_patch()),
+        )
+    }
+
     /// Returns the major version number.
     pub fn library_version_major(&self) -> i32 {
         self.inner.library_version_major()
@@ -553,6 +1920,40 @@ impl Generator {
         self.compute_hash(image_data, width, height, HashOptions::default())
     }
 
+    /// Computes a PhotoDNA hash, choosing the pixel format from a channel
+    /// count.
+    ///
+    /// 1 channel is hashed as [`PixelFormat::Gray8`], 3 as
+    /// [`PixelFormat::Rgb`] and 4 as [`PixelFormat::Rgba`]. The pixel format
+    /// in `options` is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PhotoDnaError::UnsupportedPixelFormat`] for any other
+    /// channel count, or any error from [`compute_hash`](Self::compute_hash).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let hash = generator.compute_hash_auto(&pixels, width, height, channels, HashOptions::default())?;
+    /// ```
+    pub fn compute_hash_auto(
+        &self,
+        image_data: &[u8],
+        width: u32,
+        height: u32,
+        channels: u8,
+        options: HashOptions,
+    ) -> Result<Hash> {
+        let format = match channels {
+            1 => PixelFormat::Gray8,
+            3 => PixelFormat::Rgb,
+            4 => PixelFormat::Rgba,
+            _ => return Err(PhotoDnaError::UnsupportedPixelFormat { channels }),
+        };
+        self.compute_hash(image_data, width, height, options.pixel_format(format))
+    }
+
     /// Computes a PhotoDNA hash from pixel data with custom options.
     ///
     /// # Arguments
@@ -590,10 +1991,136 @@ impl Generator {
         self.compute_hash_with_stride(image_data, width, height, 0, options)
     }
 
-    /// Computes a PhotoDNA hash with explicit stride.
+    /// Computes a PhotoDNA hash, retrying errors that may be transient.
     ///
-    /// Use this when the image has padding bytes between rows (common in
-    /// windowed framebuffers and some image formats).
+    /// Behaves like [`compute_hash`](Self::compute_hash), but when a call
+    /// fails with an error for which [`PhotoDnaError::is_recoverable`]
+    /// returns `true`, sleeps for `backoff` and tries again, up to `retries`
+    /// more times. Other errors, such as invalid input, are returned
+    /// immediately since retrying cannot fix them.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first unrecoverable error, or the last error once all
+    /// retries are used up.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use std::time::Duration;
+    ///
+    /// let hash = generator.compute_hash_with_retry(
+    ///     &image_data,
+    ///     640,
+    ///     480,
+    ///     HashOptions::new(),
+    ///     3,
+    ///     Duration::from_millis(50),
+    /// )?;
+    /// ```
+    pub fn compute_hash_with_retry(
+        &self,
+        image_data: &[u8],
+        width: u32,
+        height: u32,
+        options: HashOptions,
+        retries: u32,
+        backoff: Duration,
+    ) -> Result<Hash> {
+        let mut remaining = retries;
+        loop {
+            match self.compute_hash(image_data, width, height, options) {
+                Err(e) if e.is_recoverable() && remaining > 0 => {
+                    remaining -= 1;
+                    std::thread::sleep(backoff);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Computes a PhotoDNA hash, replacing the library instance once if it
+    /// fails.
+    ///
+    /// Behaves like [`compute_hash`](Self::compute_hash), but when the call
+    /// fails with [`PhotoDnaError::LibraryFailure`] a fresh instance is
+    /// created with [`reload`](Self::reload) and the image is hashed once
+    /// more with it. The steps are:
+    ///
+    /// 1. hash the image;
+    /// 2. on `LibraryFailure` only, replace the instance;
+    /// 3. if the reload succeeded, hash the image again and return that
+    ///    result, whatever it is.
+    ///
+    /// Other errors are returned without reloading. Replacing the library
+    /// instance is why this takes `&mut self`: no other call can be using
+    /// the old instance while it is released. For transient errors that do
+    /// not need a fresh instance, use
+    /// [`compute_hash_with_retry`](Self::compute_hash_with_retry).
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from [`compute_hash`](Self::compute_hash) other
+    /// than the first `LibraryFailure`, or the error from
+    /// [`reload`](Self::reload) if the library cannot be loaded again.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let mut generator = Generator::new(GeneratorOptions::default())?;
+    /// let hash = generator.compute_hash_resilient(&image_data, 640, 480, HashOptions::new())?;
+    /// ```
+    pub fn compute_hash_resilient(
+        &mut self,
+        image_data: &[u8],
+        width: u32,
+        height: u32,
+        options: HashOptions,
+    ) -> Result<Hash> {
+        match self.compute_hash(image_data, width, height, options) {
+            Err(PhotoDnaError::LibraryFailure) => {
+                self.reload()?;
+                self.compute_hash(image_data, width, height, options)
+            }
+            result => result,
+        }
+    }
+
+    /// Computes a PhotoDNA hash and returns it together with its Base64
+    /// encoding.
+    ///
+    /// The library produces a single output format per call, so this hashes
+    /// once in binary, as [`compute_hash`](Self::compute_hash) does, and
+    /// encodes the result with [`Hash::to_base64`]. The string matches the
+    /// library's own Base64 output and decodes back to the returned hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from [`compute_hash`](Self::compute_hash).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let (hash, base64) = generator.compute_hash_dual(&image_data, 640, 480, HashOptions::new())?;
+    /// store.insert(id.clone(), hash);
+    /// println!("{id}: {base64}");
+    /// ```
+    pub fn compute_hash_dual(
+        &self,
+        image_data: &[u8],
+        width: u32,
+        height: u32,
+        options: HashOptions,
+    ) -> Result<(Hash, String)> {
+        let hash = self.compute_hash(image_data, width, height, options)?;
+        let base64 = hash.to_base64();
+        Ok((hash, base64))
+    }
+
+    /// Computes a PhotoDNA hash with explicit stride.
+    ///
+    /// Use this when the image has padding bytes between rows (common in
+    /// windowed framebuffers and some image formats).
     ///
     /// # Arguments
     ///
@@ -614,201 +2141,707 @@ impl Generator {
         stride: u32,
         options: HashOptions,
     ) -> Result<Hash> {
-        let width_i32 = width as i32;
-        let height_i32 = height as i32;
-        let stride_i32 = stride as i32;
+        let mut hash = Hash::zeroed();
+        self.compute_hash_into(image_data, width, height, stride, options, &mut hash)?;
+        Ok(hash)
+    }
 
-        // Validate dimensions
-        if width == 0 || height == 0 {
-            return Err(PhotoDnaError::InvalidDimensions {
-                width: width_i32,
-                height: height_i32,
-            });
+    /// Computes a PhotoDNA hash from pixel data behind a raw pointer.
+    ///
+    /// For zero-copy hashing of foreign memory, such as a mapped camera or
+    /// GPU buffer, where building a `&[u8]` is awkward. `len` is checked
+    /// against the dimensions, stride and format exactly as the slice
+    /// length is in [`compute_hash_with_stride`](Self::compute_hash_with_stride),
+    /// which remains the preferred API.
+    ///
+    /// # Safety
+    ///
+    /// For the duration of the call:
+    ///
+    /// - `ptr` must be valid for reads of `len` bytes, all within a single
+    ///   allocation or mapping, and those bytes must be initialized.
+    /// - The memory must not be written by anything else, including a
+    ///   device or another thread.
+    /// - `len` must be no greater than `isize::MAX`.
+    ///
+    /// `ptr` needs no particular alignment.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PhotoDnaError::BadArgument`] if `ptr` is null, and
+    /// otherwise the same errors as
+    /// [`compute_hash_with_stride`](Self::compute_hash_with_stride).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let (ptr, len) = frame.mapped_bytes();
+    /// // SAFETY: the frame stays mapped and unmodified until it is released
+    /// // below.
+    /// let hash = unsafe { generator.compute_hash_raw(ptr, len, 1920, 1080, 0, options)? };
+    /// frame.release();
+    /// ```
+    pub unsafe fn compute_hash_raw(
+        &self,
+        ptr: *const u8,
+        len: usize,
+        width: u32,
+        height: u32,
+        stride: u32,
+        options: HashOptions,
+    ) -> Result<Hash> {
+        if ptr.is_null() {
+            return Err(PhotoDnaError::BadArgument);
         }
+        // SAFETY: `ptr` is non-null, and the caller guarantees it is valid
+        // for `len` initialized, unaliased bytes.
+        let image_data = unsafe { std::slice::from_raw_parts(ptr, len) };
+        self.compute_hash_with_stride(image_data, width, height, stride, options)
+    }
 
-        // Calculate expected buffer size
-        let bytes_per_pixel = options.pixel_format.bytes_per_pixel();
-        let expected_stride = if stride == 0 {
-            (width as usize) * bytes_per_pixel
-        } else {
-            stride as usize
-        };
-        let expected_size = expected_stride * (height as usize);
+    /// Computes a PhotoDNA hash into an existing [`Hash`](struct@Hash).
+    ///
+    /// Behaves like [`compute_hash_with_stride`](Self::compute_hash_with_stride)
+    /// but writes the result into `out`, which lets hot loops reuse one
+    /// buffer. On success `out` holds a full hash; if the library call fails
+    /// `out` is left empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the hash cannot be computed.
+    pub fn compute_hash_into(
+        &self,
+        image_data: &[u8],
+        width: u32,
+        height: u32,
+        stride: u32,
+        options: HashOptions,
+        out: &mut Hash,
+    ) -> Result<()> {
+        self.compute_hash_impl(
+            image_data,
+            width,
+            height,
+            stride,
+            options,
+            &mut self.scratch.take(),
+            out,
+        )
+    }
 
-        if image_data.len() < expected_size {
-            return Err(PhotoDnaError::BufferTooSmall {
-                expected: expected_size,
-                actual: image_data.len(),
-            });
-        }
+    /// Computes a PhotoDNA hash, converting the input in `scratch`.
+    ///
+    /// Behaves like [`compute_hash_with_stride`](Self::compute_hash_with_stride)
+    /// but reuses `scratch` for the channel reordering of BGR-family
+    /// formats instead of allocating a new buffer on every call.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the hash cannot be computed.
+    pub fn compute_hash_with_scratch(
+        &self,
+        image_data: &[u8],
+        width: u32,
+        height: u32,
+        stride: u32,
+        options: HashOptions,
+        scratch: &mut HashScratch,
+    ) -> Result<Hash> {
+        let mut hash = Hash::zeroed();
+        self.compute_hash_impl(
+            image_data,
+            width,
+            height,
+            stride,
+            options,
+            &mut scratch.buffer,
+            &mut hash,
+        )?;
+        Ok(hash)
+    }
+
+    /// Shared implementation of the single-image hash methods.
+    ///
+    /// `scratch` receives the reordered pixels when the format needs it.
+    #[allow(clippy::too_many_arguments)]
+    fn compute_hash_impl(
+        &self,
+        image_data: &[u8],
+        width: u32,
+        height: u32,
+        stride: u32,
+        options: HashOptions,
+        scratch: &mut Vec<u8>,
+        out: &mut Hash,
+    ) -> Result<()> {
+        checked_dimensions(width, height)?;
+        checked_stride(stride)?;
+        self.check_pixels(width, height)?;
 
-        let sys_options = options.to_sys_options();
+        options.validate_buffer(image_data.len(), width, height, stride)?;
+        // Only images that are padded need a buffer from the pool.
+        let mut padded = options
+            .padding_for(width, height)
+            .map(|_| self.scratch.take());
+        let (image_data, width, height, stride) = match padded.as_deref_mut() {
+            Some(padded) => pad_image(image_data, width, height, stride, options, padded),
+            None => (image_data, width, height, stride),
+        };
+        let (width_i32, height_i32) = checked_dimensions(width, height)?;
+        let stride_i32 = checked_stride(stride)?;
+        let image_data = prepare_pixels(image_data, width, height, stride, options, scratch);
 
-        // Allocate hash buffer on the stack
-        let mut hash_buffer = [0u8; HASH_SIZE];
+        let sys_options = self.sys_options(options);
+        let hash_len = hash_output_len(sys_options)?;
 
-        // SAFETY: We have validated the buffer sizes and dimensions.
+        // SAFETY: We have validated the buffer sizes and dimensions, and
+        // the output fits the hash buffer.
         // The sys library will validate the image data internally.
-        let result = unsafe {
-            self.inner.photo_dna_edge_hash(
-                image_data.as_ptr(),
-                hash_buffer.as_mut_ptr(),
-                width_i32,
-                height_i32,
-                stride_i32,
-                sys_options,
-            )
-        };
+        let mut result = Ok(0);
+        out.write_with(|buf| {
+            result = guard_ffi(|| unsafe {
+                self.inner.edge_hash(
+                    image_data.as_ptr(),
+                    buf.as_mut_ptr(),
+                    width_i32,
+                    height_i32,
+                    stride_i32,
+                    sys_options,
+                )
+            });
+            match result {
+                Ok(code) if code >= 0 => hash_len,
+                _ => 0,
+            }
+        });
 
+        let result = result?;
         if result < 0 {
             return Err(PhotoDnaError::from_error_code(result));
         }
 
-        Ok(Hash::new(hash_buffer))
+        Ok(())
+    }
+
+    /// Computes a PhotoDNA hash from separate YUV420P planes.
+    ///
+    /// The library expects the Y, U and V planes back to back in a single
+    /// buffer. This copies tightly packed planes into that layout, so
+    /// decoders that hand out one slice per plane can be hashed directly.
+    /// The pixel format in `options` is ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `y` - Luma plane, `width * height` bytes.
+    /// * `u`, `v` - Chroma planes, each `ceil(width / 2) * ceil(height / 2)`
+    ///   bytes.
+    /// * `width` - Image width in pixels (minimum 50).
+    /// * `height` - Image height in pixels (minimum 50).
+    /// * `options` - Hash computation options.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PhotoDnaError::BufferTooSmall`] if any plane is smaller
+    /// than its expected size, or any error from
+    /// [`compute_hash`](Self::compute_hash).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let hash = generator.compute_hash_yuv420p(
+    ///     frame.y(), frame.u(), frame.v(),
+    ///     frame.width(), frame.height(),
+    ///     HashOptions::default(),
+    /// )?;
+    /// ```
+    pub fn compute_hash_yuv420p(
+        &self,
+        y: &[u8],
+        u: &[u8],
+        v: &[u8],
+        width: u32,
+        height: u32,
+        options: HashOptions,
+    ) -> Result<Hash> {
+        self.compute_hash_yuv420p_impl(y, u, v, width, height, options, &mut self.scratch.take())
+    }
+
+    /// Computes a PhotoDNA hash from separate YUV420P planes, assembling
+    /// them in `scratch`.
+    ///
+    /// Behaves like [`compute_hash_yuv420p`](Self::compute_hash_yuv420p)
+    /// but reuses `scratch` for the combined buffer instead of allocating
+    /// one on every call.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`compute_hash_yuv420p`](Self::compute_hash_yuv420p).
+    #[allow(clippy::too_many_arguments)]
+    pub fn compute_hash_yuv420p_with_scratch(
+        &self,
+        y: &[u8],
+        u: &[u8],
+        v: &[u8],
+        width: u32,
+        height: u32,
+        options: HashOptions,
+        scratch: &mut HashScratch,
+    ) -> Result<Hash> {
+        self.compute_hash_yuv420p_impl(y, u, v, width, height, options, &mut scratch.buffer)
+    }
+
+    /// Shared implementation of the YUV420P plane methods.
+    #[allow(clippy::too_many_arguments)]
+    fn compute_hash_yuv420p_impl(
+        &self,
+        y: &[u8],
+        u: &[u8],
+        v: &[u8],
+        width: u32,
+        height: u32,
+        options: HashOptions,
+        buffer: &mut Vec<u8>,
+    ) -> Result<Hash> {
+        checked_dimensions(width, height)?;
+        self.check_pixels(width, height)?;
+        assemble_yuv420p(y, u, v, width, height, buffer)?;
+        self.compute_hash_with_stride(
+            buffer,
+            width,
+            height,
+            0,
+            options.pixel_format(PixelFormat::Yuv420p),
+        )
+    }
+
+    /// Computes a PhotoDNA hash from separate red, green and blue planes.
+    ///
+    /// Interleaves tightly packed planes, each `width * height` bytes, into
+    /// RGB pixels and hashes them as [`PixelFormat::Rgb`]. The pixel format
+    /// in `options` is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PhotoDnaError::BufferTooSmall`] if any plane is smaller
+    /// than `width * height` bytes, or any error from
+    /// [`compute_hash`](Self::compute_hash).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let [r, g, b] = frame.planes();
+    /// let hash = generator.compute_hash_planar_rgb(r, g, b, 640, 480, HashOptions::default())?;
+    /// ```
+    pub fn compute_hash_planar_rgb(
+        &self,
+        r: &[u8],
+        g: &[u8],
+        b: &[u8],
+        width: u32,
+        height: u32,
+        options: HashOptions,
+    ) -> Result<Hash> {
+        checked_dimensions(width, height)?;
+        self.check_pixels(width, height)?;
+        let mut buffer = self.scratch.take();
+        interleave_rgb(r, g, b, width, height, &mut buffer)?;
+        self.compute_hash_with_stride(
+            &buffer,
+            width,
+            height,
+            0,
+            options.pixel_format(PixelFormat::Rgb),
+        )
     }
 
     /// Computes a hash for a sub-region of an image.
     ///
+    /// If [`HashOptions::remove_border`] is enabled, border detection runs
+    /// inside the region and the hash of the detected content is returned
+    /// (or the region's own hash when no border is found). Use
+    /// [`compute_hash_with_border_detection`](Self::compute_hash_with_border_detection)
+    /// when you need both hashes and the content bounds.
+    ///
     /// # Arguments
     ///
     /// * `image_data` - Raw pixel data for the full image.
     /// * `width` - Full image width in pixels.
     /// * `height` - Full image height in pixels.
     /// * `stride` - Row stride in bytes, or 0 to auto-calculate.
-    /// * `region` - The sub-region to hash: (x, y, width, height).
+    /// * `region` - The sub-region to hash. A [`Region`] or an
+    ///   `(x, y, width, height)` tuple.
     /// * `options` - Hash computation options.
     ///
     /// # Errors
     ///
     /// Returns an error if the region is outside the image bounds or
     /// if the hash cannot be computed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use photodna::Region;
+    ///
+    /// let region = Region::from_corners(100, 100, 300, 250);
+    /// let hash = generator.compute_hash_subregion(&data, 640, 480, 0, region, options)?;
+    /// ```
     pub fn compute_hash_subregion(
         &self,
         image_data: &[u8],
         width: u32,
         height: u32,
         stride: u32,
-        region: (u32, u32, u32, u32),
+        region: impl Into<Region>,
         options: HashOptions,
     ) -> Result<Hash> {
-        let (rx, ry, rw, rh) = region;
+        let region = region.into();
 
-        // Validate region bounds
-        if rx + rw > width || ry + rh > height {
-            return Err(PhotoDnaError::InvalidSubImage);
-        }
+        region.check_within(width, height)?;
+        let (width_i32, height_i32) = checked_dimensions(width, height)?;
+        self.check_pixels(width, height)?;
+        let stride_i32 = checked_stride(stride)?;
 
-        let width_i32 = width as i32;
-        let height_i32 = height as i32;
-        let stride_i32 = stride as i32;
+        // Validate the buffer against the full image
+        options.validate_buffer(image_data.len(), width, height, stride)?;
+        let mut scratch = self.scratch.take();
+        let image_data = prepare_pixels(image_data, width, height, stride, options, &mut scratch);
 
-        // Validate dimensions
-        if width == 0 || height == 0 || rw == 0 || rh == 0 {
-            return Err(PhotoDnaError::InvalidDimensions {
-                width: rw as i32,
-                height: rh as i32,
-            });
-        }
+        self.hash_sub(
+            image_data,
+            (width_i32, height_i32, stride_i32),
+            region,
+            options,
+        )
+    }
 
-        // Calculate expected buffer size for the full image
-        let bytes_per_pixel = options.pixel_format.bytes_per_pixel();
-        let expected_stride = if stride == 0 {
-            (width as usize) * bytes_per_pixel
-        } else {
-            stride as usize
+    /// Computes one hash for each of several regions of an image.
+    ///
+    /// Each region is hashed independently, exactly as
+    /// [`compute_hash_subregion`](Self::compute_hash_subregion) would, but
+    /// the image is validated and prepared only once. Regions may overlap
+    /// and need not cover the image; use
+    /// [`compute_tile_hashes_with_border`](Self::compute_tile_hashes_with_border)
+    /// for border detection over a regular grid.
+    ///
+    /// # Returns
+    ///
+    /// One result per region, in the order given. A region that lies
+    /// outside the image or is smaller than [`MIN_DIMENSION`] in either
+    /// direction reports its error without stopping the remaining regions.
+    /// If the image itself is invalid, every entry carries that error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use photodna::Region;
+    ///
+    /// let regions = [Region::from_xywh(0, 0, 400, 300), Region::from_xywh(200, 150, 400, 300)];
+    /// for (region, result) in regions.iter().zip(generator.compute_hashes_for_regions(&data, 640, 480, 0, &regions, options)) {
+    ///     println!("{:?}: {:?}", region, result);
+    /// }
+    /// ```
+    pub fn compute_hashes_for_regions(
+        &self,
+        image_data: &[u8],
+        width: u32,
+        height: u32,
+        stride: u32,
+        regions: &[Region],
+        options: HashOptions,
+    ) -> Vec<Result<Hash>> {
+        let prepared = checked_dimensions(width, height).and_then(|(w, h)| {
+            self.check_pixels(width, height)?;
+            let stride_i32 = checked_stride(stride)?;
+            options.validate_buffer(image_data.len(), width, height, stride)?;
+            Ok((w, h, stride_i32))
+        });
+        let dimensions = match prepared {
+            Ok(dimensions) => dimensions,
+            Err(e) => return vec![Err(e); regions.len()],
         };
-        let expected_size = expected_stride * (height as usize);
 
-        if image_data.len() < expected_size {
-            return Err(PhotoDnaError::BufferTooSmall {
-                expected: expected_size,
-                actual: image_data.len(),
-            });
+        let mut scratch = self.scratch.take();
+        let image_data = prepare_pixels(image_data, width, height, stride, options, &mut scratch);
+
+        regions
+            .iter()
+            .map(|&region| {
+                region.check_within(width, height)?;
+                if !is_hashable_size(region.width, region.height) {
+                    return Err(PhotoDnaError::InvalidDimensions {
+                        width: region.width,
+                        height: region.height,
+                    });
+                }
+                self.hash_sub(image_data, dimensions, region, options)
+            })
+            .collect()
+    }
+
+    /// Hashes full-height windows sliding horizontally across a wide image.
+    ///
+    /// Windows are `window_width` pixels wide and start every `step`
+    /// pixels from the left edge; a step of 0 is treated as 1. The last
+    /// windows are clipped to the image, and a clipped window narrower
+    /// than [`MIN_DIMENSION`] is skipped. This lets a cropped segment be
+    /// matched against a stitched panorama. Each window is hashed as by
+    /// [`compute_hashes_for_regions`](Self::compute_hashes_for_regions),
+    /// which validates and prepares the image only once.
+    ///
+    /// # Returns
+    ///
+    /// Each window's region with its result, left to right.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// for (window, result) in generator.compute_hash_windows(&pano, 4000, 600, 0, 800, 200, options) {
+    ///     if let Some((id, _)) = blocklist.find_match(&result?, 1800.0) {
+    ///         println!("{id} found at x={}", window.x);
+    ///     }
+    /// }
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn compute_hash_windows(
+        &self,
+        image_data: &[u8],
+        width: u32,
+        height: u32,
+        stride: u32,
+        window_width: u32,
+        step: u32,
+        options: HashOptions,
+    ) -> Vec<(Region, Result<Hash>)> {
+        let windows: Vec<Region> = (0..width)
+            .step_by(step.max(1) as usize)
+            .map(|x| Region::from_xywh(x, 0, window_width.min(width - x), height))
+            .take_while(|window| window.width >= MIN_DIMENSION)
+            .collect();
+        let results =
+            self.compute_hashes_for_regions(image_data, width, height, stride, &windows, options);
+        windows.into_iter().zip(results).collect()
+    }
+
+    /// Hashes `region` of an already validated and reordered image.
+    ///
+    /// `dimensions` is the checked `(width, height, stride)` of the full
+    /// image, and `region` must have passed [`Region::check_within`].
+    fn hash_sub(
+        &self,
+        image_data: &[u8],
+        (width, height, stride): (i32, i32, i32),
+        region: Region,
+        options: HashOptions,
+    ) -> Result<Hash> {
+        let sys_options = self.sys_options(options);
+
+        // The plain sub-region entry point has no border semantics, so honour
+        // the flag through the border-aware variant instead of dropping it.
+        if options.remove_border {
+            let result =
+                self.border_hash_sub(image_data, (width, height, stride), region, sys_options)?;
+            return Ok(result.borderless.unwrap_or(result.primary));
         }
 
-        let sys_options = options.to_sys_options();
-        let mut hash_buffer = [0u8; HASH_SIZE];
+        let hash_len = hash_output_len(sys_options)?;
+        let mut hash = Hash::zeroed();
 
-        // SAFETY: Buffer sizes validated, region bounds checked.
-        let result = unsafe {
-            self.inner.photo_dna_edge_hash_sub(
+        // SAFETY: Buffer sizes validated, region bounds checked, and the
+        // output fits the hash buffer.
+        let result = guard_ffi(|| unsafe {
+            self.inner.edge_hash_sub(
                 image_data.as_ptr(),
-                hash_buffer.as_mut_ptr(),
-                width_i32,
-                height_i32,
-                stride_i32,
-                rx as i32,
-                ry as i32,
-                rw as i32,
-                rh as i32,
+                hash.as_mut_bytes().as_mut_ptr(),
+                width,
+                height,
+                stride,
+                // The region fits within the image, so its coordinates fit
+                // in i32 too.
+                region.x as i32,
+                region.y as i32,
+                region.width as i32,
+                region.height as i32,
                 sys_options,
             )
-        };
+        })?;
 
         if result < 0 {
             return Err(PhotoDnaError::from_error_code(result));
         }
 
-        Ok(Hash::new(hash_buffer))
+        hash.set_len(hash_len);
+        Ok(hash)
     }
 
-    /// Computes a hash with automatic border detection.
-    ///
-    /// This method returns both the original hash and a hash computed
-    /// after removing detected borders.
+    /// Computes a border-detecting hash for each tile of an image.
     ///
-    /// # Arguments
-    ///
-    /// * `image_data` - Raw pixel data.
-    /// * `width` - Image width in pixels (minimum 50).
-    /// * `height` - Image height in pixels (minimum 50).
-    /// * `options` - Hash computation options.
+    /// Each tile is hashed as its own sub-region with border detection, so
+    /// every region of a large scan or collage can have a border of its
+    /// own. Use [`Region::grid`] to split an image into equal tiles.
     ///
     /// # Returns
     ///
-    /// A [`BorderHashResult`] containing the primary hash and optionally
-    /// a hash with borders removed.
+    /// One result per tile, in the order given. A tile that fails, for
+    /// example because it lies outside the image or is too small or flat to
+    /// hash, reports its error without stopping the remaining tiles.
     ///
     /// # Errors
     ///
-    /// Returns an error if the hash cannot be computed.
-    pub fn compute_hash_with_border_detection(
+    /// Returns an error, without hashing any tile, if the image dimensions,
+    /// stride or buffer are invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use photodna::Region;
+    ///
+    /// let tiles = Region::grid(1200, 800, 3, 2);
+    /// let results = generator.compute_tile_hashes_with_border(&scan, 1200, 800, 0, &tiles, options)?;
+    /// for (tile, result) in tiles.iter().zip(results) {
+    ///     match result {
+    ///         Ok(hashes) => println!("{:?}: border found = {}", tile, hashes.borderless.is_some()),
+    ///         Err(e) => eprintln!("{:?}: {}", tile, e),
+    ///     }
+    /// }
+    /// ```
+    pub fn compute_tile_hashes_with_border(
         &self,
         image_data: &[u8],
         width: u32,
         height: u32,
+        stride: u32,
+        tiles: &[Region],
         options: HashOptions,
+    ) -> Result<Vec<Result<BorderHashResult>>> {
+        let (width_i32, height_i32) = checked_dimensions(width, height)?;
+        self.check_pixels(width, height)?;
+        let stride_i32 = checked_stride(stride)?;
+
+        options.validate_buffer(image_data.len(), width, height, stride)?;
+        let mut scratch = self.scratch.take();
+        let image_data = prepare_pixels(image_data, width, height, stride, options, &mut scratch);
+
+        let results = tiles
+            .iter()
+            .map(|&tile| {
+                tile.check_within(width, height)?;
+                self.border_hash_sub(
+                    image_data,
+                    (width_i32, height_i32, stride_i32),
+                    tile,
+                    self.sys_options(options),
+                )
+            })
+            .collect();
+        Ok(results)
+    }
+
+    /// Runs border detection inside `region` of an already validated and
+    /// reordered image.
+    ///
+    /// `dimensions` is the checked `(width, height, stride)` of the full
+    /// image, and `region` must have passed [`Region::check_within`].
+    fn border_hash_sub(
+        &self,
+        image_data: &[u8],
+        (width, height, stride): (i32, i32, i32),
+        region: Region,
+        sys_options: PhotoDnaOptions,
     ) -> Result<BorderHashResult> {
-        let width_i32 = width as i32;
-        let height_i32 = height as i32;
+        let mut hash_results = [sys::HashResult::default(); 2];
 
-        // Validate dimensions
-        if width == 0 || height == 0 {
-            return Err(PhotoDnaError::InvalidDimensions {
-                width: width_i32,
-                height: height_i32,
-            });
+        // SAFETY: Buffer validated, region bounds checked, hash_results
+        // holds the two entries advertised.
+        let count = guard_ffi(|| unsafe {
+            self.inner.edge_hash_border_sub(
+                image_data.as_ptr(),
+                hash_results.as_mut_ptr(),
+                2,
+                width,
+                height,
+                stride,
+                region.x as i32,
+                region.y as i32,
+                region.width as i32,
+                region.height as i32,
+                sys_options,
+            )
+        })?;
+
+        if count < 0 {
+            return Err(PhotoDnaError::from_error_code(count));
         }
 
-        // Calculate expected buffer size
-        let bytes_per_pixel = options.pixel_format.bytes_per_pixel();
-        let expected_size = (width as usize) * (height as usize) * bytes_per_pixel;
+        // The content hash follows the full-region hash when a border was found.
+        let primary = Hash::try_from(&hash_results[0])?;
+        let (borderless, content_region) = if count >= 2 {
+            let hash = Hash::try_from(&hash_results[1])?;
+            (Some(hash), Region::from_hash_result(&hash_results[1]))
+        } else {
+            (None, None)
+        };
 
-        if image_data.len() < expected_size {
-            return Err(PhotoDnaError::BufferTooSmall {
-                expected: expected_size,
-                actual: image_data.len(),
-            });
-        }
+        Ok(BorderHashResult {
+            primary,
+            borderless,
+            content_region,
+        })
+    }
 
-        let sys_options = options.to_sys_options();
+    /// Computes a hash with automatic border detection.
+    ///
+    /// This method returns both the original hash and a hash computed
+    /// after removing detected borders.
+    ///
+    /// # Arguments
+    ///
+    /// * `image_data` - Raw pixel data.
+    /// * `width` - Image width in pixels (minimum 50).
+    /// * `height` - Image height in pixels (minimum 50).
+    /// * `options` - Hash computation options.
+    ///
+    /// # Returns
+    ///
+    /// A [`BorderHashResult`] containing the primary hash and optionally
+    /// a hash with borders removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the hash cannot be computed.
+    pub fn compute_hash_with_border_detection(
+        &self,
+        image_data: &[u8],
+        width: u32,
+        height: u32,
+        options: HashOptions,
+    ) -> Result<BorderHashResult> {
+        checked_dimensions(width, height)?;
+        self.check_pixels(width, height)?;
+
+        options.validate_buffer(image_data.len(), width, height, 0)?;
+        let mut padded = options
+            .padding_for(width, height)
+            .map(|_| self.scratch.take());
+        let (image_data, width, height) = match padded.as_deref_mut() {
+            Some(padded) => {
+                let (data, width, height, _) =
+                    pad_image(image_data, width, height, 0, options, padded);
+                (data, width, height)
+            }
+            None => (image_data, width, height),
+        };
+        let (width_i32, height_i32) = checked_dimensions(width, height)?;
+        let mut scratch = self.scratch.take();
+        let image_data = prepare_pixels(image_data, width, height, 0, options, &mut scratch);
+
+        let sys_options = self.sys_options(options);
 
         // Allocate result buffer for up to 2 hashes
         let mut hash_results = [sys::HashResult::default(); 2];
 
         // SAFETY: Buffer validated, hash_results array is properly sized.
-        let count = unsafe {
-            self.inner.photo_dna_edge_hash_border(
+        let count = guard_ffi(|| unsafe {
+            self.inner.edge_hash_border(
                 image_data.as_ptr(),
                 hash_results.as_mut_ptr(),
                 2,
@@ -817,25 +2850,19 @@ impl Generator {
                 0, // auto stride
                 sys_options,
             )
-        };
+        })?;
 
         if count < 0 {
             return Err(PhotoDnaError::from_error_code(count));
         }
 
         // Extract primary hash (always present if count >= 1)
-        let primary = extract_hash_from_result(&hash_results[0])?;
+        let primary = Hash::try_from(&hash_results[0])?;
 
         // Extract borderless hash if a border was detected (count == 2)
         let (borderless, content_region) = if count >= 2 {
-            let hash = extract_hash_from_result(&hash_results[1])?;
-            let region = (
-                hash_results[1].header_dimensions_image_x,
-                hash_results[1].header_dimensions_image_y,
-                hash_results[1].header_dimensions_image_w,
-                hash_results[1].header_dimensions_image_h,
-            );
-            (Some(hash), Some(region))
+            let hash = Hash::try_from(&hash_results[1])?;
+            (Some(hash), Region::from_hash_result(&hash_results[1]))
         } else {
             (None, None)
         };
@@ -847,6 +2874,117 @@ impl Generator {
         })
     }
 
+    /// Hashes a sequence of images, reporting progress after each one.
+    ///
+    /// `on_progress` is called with `(done, total)` after every image,
+    /// whether it hashed successfully or not. `total` is the exact length
+    /// reported by the iterator, or 0 if the length is not known up front.
+    ///
+    /// # Returns
+    ///
+    /// One result per input image, in input order. A failure for one image
+    /// does not stop the remaining images from being hashed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// use photodna::ImageRef;
+    ///
+    /// let images: Vec<ImageRef> = frames.iter().map(|f| ImageRef::new(f, 640, 480)).collect();
+    /// let hashes = generator.hash_corpus(images, HashOptions::new(), |done, total| {
+    ///     eprintln!("{done}/{total}");
+    /// });
+    /// ```
+    pub fn hash_corpus<'a, I, F>(
+        &self,
+        items: I,
+        options: HashOptions,
+        mut on_progress: F,
+    ) -> Vec<Result<Hash>>
+    where
+        I: IntoIterator<Item = ImageRef<'a>>,
+        F: FnMut(usize, usize),
+    {
+        let items = items.into_iter();
+        let total = match items.size_hint() {
+            (lower, Some(upper)) if lower == upper => upper,
+            _ => 0,
+        };
+
+        let mut results = Vec::with_capacity(total);
+        for image in items {
+            results.push(self.compute_hash_with_stride(
+                image.data,
+                image.width,
+                image.height,
+                image.stride,
+                options,
+            ));
+            on_progress(results.len(), total);
+        }
+        results
+    }
+
+    /// Hashes a sequence of tightly packed images with shared options.
+    ///
+    /// Each item is `(image_data, width, height)`; the stride is always
+    /// calculated from the width and pixel format. Use
+    /// [`hash_corpus`](Self::hash_corpus) for padded rows or progress
+    /// reporting.
+    ///
+    /// # Returns
+    ///
+    /// One result per input image, in input order. A failure for one image
+    /// does not stop the remaining images from being hashed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let items = frames.iter().map(|f| (f.as_slice(), 640, 480));
+    /// let hashes = generator.compute_many(items, HashOptions::new());
+    /// ```
+    pub fn compute_many<'a>(
+        &self,
+        items: impl IntoIterator<Item = (&'a [u8], u32, u32)>,
+        options: HashOptions,
+    ) -> Vec<Result<Hash>> {
+        items
+            .into_iter()
+            .map(|(image_data, width, height)| {
+                self.compute_hash_with_stride(image_data, width, height, 0, options)
+            })
+            .collect()
+    }
+
+    /// Checks an image's pixel count against
+    /// [`GeneratorOptions::max_pixels`].
+    pub(crate) fn check_pixels(&self, width: u32, height: u32) -> Result<()> {
+        if u64::from(width) * u64::from(height) > self.max_pixels {
+            return Err(PhotoDnaError::ImageTooLarge {
+                width,
+                height,
+                max_pixels: self.max_pixels,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the path of the dynamic library this generator loaded.
+    ///
+    /// Returns `None` for generators that are not backed by the native
+    /// library, such as stub generators.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// if let Some(path) = generator.loaded_library_path() {
+    ///     println!("Using PhotoDNA from {}", path.display());
+    /// }
+    /// ```
+    pub fn loaded_library_path(&self) -> Option<&Path> {
+        self.inner.library_path()
+    }
+
     /// Returns the raw library instance pointer.
     ///
     /// This is intended for advanced use cases that need direct FFI access.
@@ -858,31 +2996,310 @@ impl Generator {
     pub fn raw_instance(&self) -> *mut c_void {
         self.inner.raw_instance()
     }
+
+    /// Converts hash options to library flags, forcing memory checking
+    /// while calls remain from [`GeneratorOptions::check_memory_first_n`].
+    fn sys_options(&self, options: HashOptions) -> PhotoDnaOptions {
+        let check_first = self
+            .check_memory_calls
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+            .is_ok();
+        options
+            .check_memory(options.check_memory || check_first)
+            .to_sys_options()
+    }
 }
 
-// SAFETY: The Generator can be sent between threads. The internal library
-// handle is thread-safe for single-owner usage (ownership transfer).
-#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
-unsafe impl Send for Generator {}
+/// The library version [`REFERENCE_EXPECTED_HASH`] was recorded with.
+pub(crate) const REFERENCE_LIBRARY_VERSION: Option<&str> = None;
 
-// Note: Generator is NOT Sync because the underlying library may maintain
-// thread-local state. Use Mutex if concurrent access is needed.
+/// The hash of [`reference_image`] under [`REFERENCE_LIBRARY_VERSION`],
+/// checked by [`Generator::verify`]. `None` until recorded from the SDK.
+pub(crate) const REFERENCE_EXPECTED_HASH: Option<[u8; HASH_SIZE]> = None;
+
+/// Returns the RGB reference image used by [`Generator::verify`] and its
+/// side length.
+///
+/// A smooth two-axis gradient with a checkerboard overlay, so the image
+/// has gradients at every scale.
+pub(crate) fn reference_image() -> (Vec<u8>, u32) {
+    const SIDE: u32 = 64;
+    let mut image = Vec::with_capacity((SIDE * SIDE * 3) as usize);
+    for y in 0..SIDE {
+        for x in 0..SIDE {
+            let checker = if (x / 8 + y / 8) % 2 == 0 { 0 } else { 64 };
+            image.extend_from_slice(&[(x * 3) as u8, (y * 3) as u8, checker + ((x + y) as u8)]);
+        }
+    }
+    (image, SIDE)
+}
+
+/// Converts image dimensions to the library's `i32` representation.
+///
+/// Rejects zero dimensions and values above `i32::MAX`, which would
+/// otherwise wrap to negative numbers at the FFI boundary.
+fn checked_dimensions(width: u32, height: u32) -> Result<(i32, i32)> {
+    match (i32::try_from(width), i32::try_from(height)) {
+        (Ok(w), Ok(h)) if w > 0 && h > 0 => Ok((w, h)),
+        _ => Err(PhotoDnaError::InvalidDimensions { width, height }),
+    }
+}
+
+/// Returns the number of hash bytes the library writes for `options`.
+///
+/// The safe layer always requests binary Edge V2 hashes, which fill a
+/// [`Hash`] exactly. A format with longer output (Base64) would overrun the
+/// hash buffer, so it is rejected before the library is called.
+fn hash_output_len(options: PhotoDnaOptions) -> Result<usize> {
+    match sys::hash_size_for_options(options) {
+        len if len <= HASH_SIZE => Ok(len),
+        _ => Err(PhotoDnaError::InvalidHash),
+    }
+}
+
+/// Converts a row stride to the library's `i32` representation.
+fn checked_stride(stride: u32) -> Result<i32> {
+    i32::try_from(stride).map_err(|_| PhotoDnaError::InvalidStride)
+}
+
+/// Copies tightly packed YUV420P planes into `buffer`, replacing its
+/// contents, after checking each plane's size.
+fn assemble_yuv420p(
+    y: &[u8],
+    u: &[u8],
+    v: &[u8],
+    width: u32,
+    height: u32,
+    buffer: &mut Vec<u8>,
+) -> Result<()> {
+    let (width, height) = (width as usize, height as usize);
+    let luma_len = width.saturating_mul(height);
+    let chroma_len = (width / 2 + width % 2).saturating_mul(height / 2 + height % 2);
+
+    for (plane, expected) in [(y, luma_len), (u, chroma_len), (v, chroma_len)] {
+        if plane.len() < expected {
+            return Err(PhotoDnaError::BufferTooSmall {
+                expected,
+                actual: plane.len(),
+            });
+        }
+    }
+
+    buffer.clear();
+    buffer.extend_from_slice(&y[..luma_len]);
+    buffer.extend_from_slice(&u[..chroma_len]);
+    buffer.extend_from_slice(&v[..chroma_len]);
+    Ok(())
+}
+
+/// Interleaves tightly packed R, G and B planes into RGB pixels in
+/// `buffer`, replacing its contents, after checking each plane's size.
+fn interleave_rgb(
+    r: &[u8],
+    g: &[u8],
+    b: &[u8],
+    width: u32,
+    height: u32,
+    buffer: &mut Vec<u8>,
+) -> Result<()> {
+    let plane_len = (width as usize).saturating_mul(height as usize);
+    for plane in [r, g, b] {
+        if plane.len() < plane_len {
+            return Err(PhotoDnaError::BufferTooSmall {
+                expected: plane_len,
+                actual: plane.len(),
+            });
+        }
+    }
+
+    buffer.clear();
+    buffer.reserve(plane_len * 3);
+    let (r, g, b) = (&r[..plane_len], &g[..plane_len], &b[..plane_len]);
+    for ((&red, &green), &blue) in r.iter().zip(g).zip(b) {
+        buffer.extend_from_slice(&[red, green, blue]);
+    }
+    Ok(())
+}
+
+/// Converts pixels into a layout the library reads as intended.
+///
+/// Reorders the channels of formats the library cannot read directly, then
+/// premultiplies or unpremultiplies alpha if [`HashOptions::alpha_mode`]
+/// differs from what the format declares. [`PixelFormat::Gray32`] values
+/// are byteswapped if [`HashOptions::gray32_endian`] is not native. The
+/// converted pixels are written to `scratch`, replacing its contents.
+/// Returns the input unchanged when no conversion is needed. The buffer
+/// must already have been validated for the format; padding bytes between
+/// rows are copied as-is.
+fn prepare_pixels<'a>(
+    data: &'a [u8],
+    width: u32,
+    height: u32,
+    stride: u32,
+    options: HashOptions,
+    scratch: &'a mut Vec<u8>,
+) -> &'a [u8] {
+    let format = options.pixel_format;
+    let swizzle = format.channel_order().and_then(ChannelOrder::swizzle);
+    let alpha = match (format.alpha(), options.alpha_mode) {
+        (Some((index, expected)), Some(mode)) if mode != expected => Some((index, expected)),
+        _ => None,
+    };
+    let byteswap = format == PixelFormat::Gray32
+        && options
+            .gray32_endian
+            .is_some_and(|endian| endian != Endian::NATIVE);
+    if swizzle.is_none() && alpha.is_none() && !byteswap {
+        return data;
+    }
+
+    let channels = format.bytes_per_pixel();
+    let row_pixels = width as usize * channels;
+    let row_len = if stride == 0 {
+        row_pixels
+    } else {
+        stride as usize
+    };
+
+    scratch.clear();
+    scratch.extend_from_slice(data);
+    for row in scratch.chunks_mut(row_len).take(height as usize) {
+        let row_pixels = row_pixels.min(row.len());
+        for pixel in row[..row_pixels].chunks_exact_mut(channels) {
+            if let Some(swizzle) = swizzle {
+                let mut source = [0u8; 4];
+                source[..channels].copy_from_slice(pixel);
+                for (dst, &src) in pixel.iter_mut().zip(swizzle) {
+                    *dst = source[src];
+                }
+            }
+            if let Some((index, target)) = alpha {
+                convert_alpha(pixel, index, target);
+            }
+            if byteswap {
+                pixel.reverse();
+            }
+        }
+    }
+    scratch
+}
+
+/// Pads an image smaller than [`MIN_DIMENSION`] as requested by
+/// [`HashOptions::pad_to_minimum`].
+///
+/// The padded, tightly packed image is written to `scratch`, replacing its
+/// contents, and returned with its width, height and stride. Returns the
+/// input unchanged when no padding applies. The buffer must already have
+/// been validated and the dimensions must be non-zero.
+fn pad_image<'a>(
+    data: &'a [u8],
+    width: u32,
+    height: u32,
+    stride: u32,
+    options: HashOptions,
+    scratch: &'a mut Vec<u8>,
+) -> (&'a [u8], u32, u32, u32) {
+    let Some(mode) = options.padding_for(width, height) else {
+        return (data, width, height, stride);
+    };
+
+    let channels = options.pixel_format.bytes_per_pixel();
+    let row_len = if stride == 0 {
+        width as usize * channels
+    } else {
+        stride as usize
+    };
+    let (new_width, new_height) = (width.max(MIN_DIMENSION), height.max(MIN_DIMENSION));
+    let left = i64::from((new_width - width) / 2);
+    let top = i64::from((new_height - height) / 2);
+
+    // Only constant padding has pixels without a source.
+    let fill = match mode {
+        PadMode::Constant(value) => value,
+        PadMode::Edge | PadMode::Reflect => 0,
+    };
+
+    scratch.clear();
+    scratch.reserve(new_width as usize * new_height as usize * channels);
+    for y in 0..i64::from(new_height) {
+        let row = mode.source(y - top, height).map(|sy| &data[sy * row_len..]);
+        for x in 0..i64::from(new_width) {
+            match (row, mode.source(x - left, width)) {
+                (Some(row), Some(sx)) => {
+                    scratch.extend_from_slice(&row[sx * channels..(sx + 1) * channels]);
+                }
+                _ => scratch.extend(std::iter::repeat(fill).take(channels)),
+            }
+        }
+    }
+    (scratch, new_width, new_height, 0)
+}
 
-/// Extracts a Hash from a sys::HashResult.
-fn extract_hash_from_result(result: &sys::HashResult) -> Result<Hash> {
-    // Copy packed field to avoid unaligned access
-    let result_code = result.result;
-    if result_code < 0 {
-        return Err(PhotoDnaError::from_error_code(result_code));
+/// Converts one pixel's color channels to `target` alpha, with alpha at
+/// `alpha_index`.
+fn convert_alpha(pixel: &mut [u8], alpha_index: usize, target: AlphaMode) {
+    let alpha = u32::from(pixel[alpha_index]);
+    for (i, channel) in pixel.iter_mut().enumerate() {
+        if i == alpha_index {
+            continue;
+        }
+        let c = u32::from(*channel);
+        *channel = match target {
+            AlphaMode::Premultiplied => (c * alpha + 127) / 255,
+            AlphaMode::Straight if alpha == 0 => 0,
+            AlphaMode::Straight => ((c * 255 + alpha / 2) / alpha).min(255),
+        } as u8;
+    }
+}
+
+/// Number of library calls in progress across all generators.
+static INFLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts a call in [`INFLIGHT`] for as long as it is alive.
+struct InflightGuard;
+
+impl InflightGuard {
+    fn enter() -> Self {
+        INFLIGHT.fetch_add(1, Ordering::Relaxed);
+        Self
+    }
+}
+
+impl Drop for InflightGuard {
+    fn drop(&mut self) {
+        INFLIGHT.fetch_sub(1, Ordering::Relaxed);
     }
+}
 
-    // The hash is stored in the first HASH_SIZE bytes
-    let mut hash_bytes = [0u8; HASH_SIZE];
-    hash_bytes.copy_from_slice(&result.hash[..HASH_SIZE]);
+/// Runs a library call, converting an unwind into an error when the
+/// `catch-ffi` feature is enabled.
+///
+/// The call is counted by [`Generator::inflight`] while it runs.
+#[inline]
+fn guard_ffi<T>(call: impl FnOnce() -> T) -> Result<T> {
+    let _inflight = InflightGuard::enter();
 
-    Ok(Hash::new(hash_bytes))
+    #[cfg(feature = "catch-ffi")]
+    {
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(call))
+            .map_err(|_| PhotoDnaError::LibraryFailure)
+    }
+
+    #[cfg(not(feature = "catch-ffi"))]
+    {
+        Ok(call())
+    }
 }
 
+// SAFETY: The Generator can be sent between threads. It exclusively owns its
+// backend. Backends installed with `from_backend` are required to be `Send`;
+// the native library handle, which is not, is thread-safe for single-owner
+// usage (ownership transfer).
+unsafe impl Send for Generator {}
+
+// Note: Generator is NOT Sync because the underlying library may maintain
+// thread-local state. Use Mutex if concurrent access is needed.
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -894,6 +3311,68 @@ mod tests {
         assert_eq!(PixelFormat::Gray8.bytes_per_pixel(), 1);
     }
 
+    #[test]
+    fn test_pixel_format_required_buffer_size() {
+        let cases = [
+            (PixelFormat::Rgb, 30_000),
+            (PixelFormat::Bgr, 30_000),
+            (PixelFormat::YCbCr, 30_000),
+            (PixelFormat::Rgba, 40_000),
+            (PixelFormat::RgbaPremultiplied, 40_000),
+            (PixelFormat::Bgra, 40_000),
+            (PixelFormat::Argb, 40_000),
+            (PixelFormat::Abgr, 40_000),
+            (PixelFormat::Cmyk, 40_000),
+            (PixelFormat::Gray32, 40_000),
+            (PixelFormat::Gray8, 10_000),
+            (PixelFormat::Yuv420p, 15_000),
+        ];
+        for (format, expected) in cases {
+            assert_eq!(
+                format.required_buffer_size(100, 100, 0),
+                expected,
+                "{:?}",
+                format
+            );
+        }
+
+        // Odd dimensions round the chroma planes up
+        assert_eq!(
+            PixelFormat::Yuv420p.required_buffer_size(51, 51, 0),
+            2601 + 2 * 26 * 26
+        );
+        // Explicit stride
+        assert_eq!(PixelFormat::Cmyk.required_buffer_size(100, 10, 512), 5120);
+        // No overflow on huge inputs
+        assert_eq!(
+            PixelFormat::Rgba.required_buffer_size(u32::MAX, u32::MAX, 0),
+            (u32::MAX as usize)
+                .saturating_mul(4)
+                .saturating_mul(u32::MAX as usize)
+        );
+    }
+
+    #[test]
+    fn test_pixel_format_validate_buffer() {
+        assert!(PixelFormat::Cmyk
+            .validate_buffer(40_000, 100, 100, 0)
+            .is_ok());
+        assert_eq!(
+            PixelFormat::Cmyk.validate_buffer(30_000, 100, 100, 0),
+            Err(PhotoDnaError::BufferTooSmall {
+                expected: 40_000,
+                actual: 30_000
+            })
+        );
+        assert_eq!(
+            PixelFormat::YCbCr.validate_buffer(29_999, 100, 100, 0),
+            Err(PhotoDnaError::BufferTooSmall {
+                expected: 30_000,
+                actual: 29_999
+            })
+        );
+    }
+
     #[test]
     fn test_generator_options_builder() {
         let options = GeneratorOptions::new()
@@ -904,6 +3383,39 @@ mod tests {
         assert_eq!(options.library_dir, Some("/custom/path".to_string()));
     }
 
+    #[test]
+    fn test_pixel_format_names_roundtrip() {
+        assert_eq!(PixelFormat::all().len(), 12);
+        for &format in PixelFormat::all() {
+            assert_eq!(PixelFormat::from_name(format.name()), Some(format));
+            assert_eq!(
+                format
+                    .name()
+                    .to_ascii_uppercase()
+                    .parse::<PixelFormat>()
+                    .unwrap(),
+                format
+            );
+        }
+        assert!(matches!(
+            "rgb565".parse::<PixelFormat>(),
+            Err(PhotoDnaError::SourceFormatUnknown)
+        ));
+    }
+
+    #[test]
+    fn test_is_hashable_size() {
+        assert!(!is_hashable_size(49, 49));
+        assert!(!is_hashable_size(49, 51));
+        assert!(!is_hashable_size(51, 49));
+        assert!(is_hashable_size(50, 50));
+        assert!(is_hashable_size(51, 51));
+
+        let options = HashOptions::new().remove_border(true);
+        assert!(!options.can_hash_dimensions(49, 50));
+        assert!(options.can_hash_dimensions(50, 50));
+    }
+
     #[test]
     fn test_hash_options_builder() {
         let options = HashOptions::new()
@@ -932,6 +3444,1226 @@ mod tests {
         assert!(sys_opts & sys::PhotoDna_NoRotateFlip != 0);
     }
 
+    #[test]
+    fn test_region_constructors() {
+        let region = Region::from_xywh(10, 20, 30, 40);
+        assert_eq!(Region::from_corners(10, 20, 40, 60), region);
+        assert_eq!(Region::from_corners(40, 60, 10, 20), region);
+        assert_eq!(Region::from((10, 20, 30, 40)), region);
+    }
+
+    #[test]
+    fn test_region_fits_within() {
+        assert!(Region::from_xywh(0, 0, 100, 100).fits_within(100, 100));
+        assert!(!Region::from_xywh(1, 0, 100, 100).fits_within(100, 100));
+        assert!(!Region::from_xywh(u32::MAX, 0, 1, 1).fits_within(100, 100));
+    }
+
+    #[test]
+    fn test_region_from_hash_result() {
+        let mut result = sys::HashResult {
+            header_dimensions_image_x: 5,
+            header_dimensions_image_y: 6,
+            header_dimensions_image_w: 70,
+            header_dimensions_image_h: 80,
+            ..Default::default()
+        };
+        assert_eq!(
+            Region::from_hash_result(&result),
+            Some(Region::from_xywh(5, 6, 70, 80))
+        );
+
+        result.header_dimensions_image_x = -1;
+        assert_eq!(Region::from_hash_result(&result), None);
+    }
+
+    #[test]
+    fn test_hash_len_matches_output_format() {
+        let generator = Generator::from_backend(backend::mock::MockBackend::default());
+        let pixels = vec![0u8; 64 * 64 * 3];
+        let options = HashOptions::new();
+
+        let expected = sys::hash_size_for_options(generator.sys_options(options));
+        let hash = generator.compute_hash(&pixels, 64, 64, options).unwrap();
+        assert_eq!(hash.len(), expected);
+        let sub = generator
+            .compute_hash_subregion(&pixels, 64, 64, 0, (0, 0, 60, 60), options)
+            .unwrap();
+        assert_eq!(sub.len(), expected);
+
+        assert_eq!(
+            hash_output_len(sys::PhotoDna_HashFormatEdgeV2),
+            Ok(HASH_SIZE)
+        );
+        assert_eq!(
+            hash_output_len(sys::PhotoDna_HashFormatEdgeV2Base64),
+            Err(PhotoDnaError::InvalidHash)
+        );
+    }
+
+    #[test]
+    fn test_output_size() {
+        const DEFAULT_SIZE: usize = HashOptions {
+            pixel_format: PixelFormat::Rgb,
+            remove_border: false,
+            no_rotate_flip: false,
+            verbose: false,
+            check_memory: false,
+            skip_buffer_validation: false,
+            alpha_mode: None,
+            gray32_endian: None,
+            pad_to_minimum: None,
+            #[cfg(feature = "image")]
+            apply_exif_orientation: false,
+        }
+        .output_size();
+        assert_eq!(DEFAULT_SIZE, HASH_SIZE);
+
+        // The safe layer always requests binary hashes.
+        for &format in PixelFormat::all() {
+            let options = HashOptions::new()
+                .pixel_format(format)
+                .remove_border(true)
+                .verbose(true);
+            let flags = options.to_sys_options();
+            assert_eq!(
+                flags & sys::PhotoDna_HashFormatMask,
+                sys::PhotoDna_HashFormatEdgeV2
+            );
+            assert_eq!(options.output_size(), sys::hash_size_for_options(flags));
+            assert_eq!(options.output_size(), HASH_SIZE);
+        }
+
+        // Base64 output, which the safe layer never requests, is longer.
+        let base64 = sys::hash_size_for_options(sys::PhotoDna_HashFormatEdgeV2Base64);
+        assert_eq!(base64, HASH_SIZE_MAX);
+        assert!(base64 > HashOptions::new().output_size());
+    }
+
+    #[test]
+    fn test_compute_hash_auto() {
+        let backend = backend::mock::MockBackend::default();
+        let seen = backend.options.clone();
+        let generator = Generator::from_backend(backend);
+
+        for (channels, format) in [
+            (1, PixelFormat::Gray8),
+            (3, PixelFormat::Rgb),
+            (4, PixelFormat::Rgba),
+        ] {
+            let pixels = vec![0u8; 64 * 64 * channels as usize];
+            let options = HashOptions::new().pixel_format(PixelFormat::Cmyk);
+            generator
+                .compute_hash_auto(&pixels, 64, 64, channels, options)
+                .unwrap();
+            let expected = generator.sys_options(HashOptions::new().pixel_format(format));
+            assert_eq!(seen.lock().unwrap().last().copied(), Some(expected));
+        }
+
+        let pixels = vec![0u8; 64 * 64 * 2];
+        assert!(matches!(
+            generator.compute_hash_auto(&pixels, 64, 64, 2, HashOptions::new()),
+            Err(PhotoDnaError::UnsupportedPixelFormat { channels: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_library_version_decoding() {
+        let cases = [
+            (0, (0, 0), "0.0"),
+            ((1 << 16) | 5, (1, 5), "1.5"),
+            ((12 << 16) | 345, (12, 345), "12.345"),
+            (-1, (u16::MAX, u16::MAX), "65535.65535"),
+        ];
+        for (packed, (major, minor), text) in cases {
+            let version = LibraryVersion::from(packed);
+            assert_eq!(version, LibraryVersion { major, minor });
+            assert_eq!(version.to_string(), text);
+        }
+        assert!(LibraryVersion::from((1 << 16) | 9) < LibraryVersion::from(2 << 16));
+    }
+
+    #[test]
+    fn test_version_tuple_from_backend() {
+        let generator = Generator::from_backend(backend::mock::MockBackend {
+            version: (1, 5, 3),
+            ..Default::default()
+        });
+        assert_eq!(generator.version_tuple(), (1, 5, 3));
+        assert_eq!(generator.library_version(), (1 << 16) | 5);
+        assert_eq!(generator.version(), LibraryVersion { major: 1, minor: 5 });
+
+        let generator = Generator::from_backend(backend::mock::MockBackend {
+            version: (-1, 70_000, 0),
+            ..Default::default()
+        });
+        assert_eq!(generator.version_tuple(), (0, u16::MAX, 0));
+        assert_eq!(generator.loaded_library_path(), None);
+    }
+
+    #[test]
+    fn test_subregion_routes_remove_border_to_border_sub() {
+        let data = vec![0u8; 100 * 100 * 3];
+        let region = Region::from_xywh(10, 10, 60, 60);
+        let backend = backend::mock::MockBackend::default();
+        let calls = backend.calls.clone();
+        let generator = Generator::from_backend(backend);
+
+        generator
+            .compute_hash_subregion(&data, 100, 100, 0, region, HashOptions::new())
+            .unwrap();
+        assert_eq!(calls.lock().unwrap().first(), Some(&"edge_hash_sub"));
+
+        calls.lock().unwrap().clear();
+        let hash = generator
+            .compute_hash_subregion(
+                &data,
+                100,
+                100,
+                0,
+                region,
+                HashOptions::new().remove_border(true),
+            )
+            .unwrap();
+        assert_eq!(calls.lock().unwrap().first(), Some(&"edge_hash_border_sub"));
+        assert_eq!(hash.as_bytes()[0], 0xAB);
+    }
+
+    #[test]
+    fn test_assemble_yuv420p_layout() {
+        // 51x51 has 26x26 chroma planes.
+        let (y, u, v) = (vec![1u8; 2601], vec![2u8; 676], vec![3u8; 700]);
+        let mut buffer = vec![9u8; 10];
+        assemble_yuv420p(&y, &u, &v, 51, 51, &mut buffer).unwrap();
+
+        assert_eq!(
+            buffer.len(),
+            PixelFormat::Yuv420p.required_buffer_size(51, 51, 0)
+        );
+        assert!(buffer[..2601].iter().all(|&b| b == 1));
+        assert!(buffer[2601..3277].iter().all(|&b| b == 2));
+        assert!(buffer[3277..].iter().all(|&b| b == 3));
+
+        assert_eq!(
+            assemble_yuv420p(&y, &u[..675], &v, 51, 51, &mut buffer),
+            Err(PhotoDnaError::BufferTooSmall {
+                expected: 676,
+                actual: 675
+            })
+        );
+    }
+
+    #[test]
+    fn test_compute_hash_yuv420p() {
+        let generator = Generator::from_backend(backend::mock::MockBackend::default());
+        let (y, chroma) = (vec![0u8; 64 * 64], vec![128u8; 32 * 32]);
+
+        let hash = generator
+            .compute_hash_yuv420p(&y, &chroma, &chroma, 64, 64, HashOptions::new())
+            .unwrap();
+        assert_eq!(hash.as_bytes()[0], 0xAB);
+
+        assert!(matches!(
+            generator.compute_hash_yuv420p(&y[..100], &chroma, &chroma, 64, 64, HashOptions::new()),
+            Err(PhotoDnaError::BufferTooSmall { .. })
+        ));
+    }
+
+    #[test]
+    fn test_compute_hash_planar_rgb() {
+        let generator = Generator::from_backend(backend::mock::MockBackend {
+            echo_input: true,
+            ..Default::default()
+        });
+        let plane = |f: fn(usize) -> u8| (0..64 * 64).map(f).collect::<Vec<u8>>();
+        let (r, g, b) = (plane(|i| i as u8), plane(|i| (i / 64) as u8), plane(|_| 7));
+
+        let hash = generator
+            .compute_hash_planar_rgb(&r, &g, &b, 64, 64, HashOptions::new())
+            .unwrap();
+        let interleaved: Vec<u8> = (0..64 * 64).flat_map(|i| [r[i], g[i], b[i]]).collect();
+        assert_eq!(hash.as_bytes(), &interleaved[..HASH_SIZE]);
+
+        assert_eq!(
+            generator.compute_hash_planar_rgb(&r, &g, &b[..100], 64, 64, HashOptions::new()),
+            Err(PhotoDnaError::BufferTooSmall {
+                expected: 64 * 64,
+                actual: 100
+            })
+        );
+    }
+
+    #[test]
+    fn test_compute_hash_into() {
+        let generator = Generator::from_backend(backend::mock::MockBackend::default());
+        let pixels = vec![0u8; 64 * 64 * 3];
+        let mut hash = Hash::zeroed();
+
+        generator
+            .compute_hash_into(&pixels, 64, 64, 0, HashOptions::new(), &mut hash)
+            .unwrap();
+        assert!(hash.is_full());
+        assert_eq!(hash.as_bytes()[0], 0xAB);
+
+        let failing = Generator::from_backend(backend::mock::MockBackend {
+            hash_return: sys::PhotoDna_ErrorImageIsFlat,
+            ..Default::default()
+        });
+        assert!(failing
+            .compute_hash_into(&pixels, 64, 64, 0, HashOptions::new(), &mut hash)
+            .is_err());
+        assert!(hash.is_empty());
+    }
+
+    #[test]
+    fn test_compute_hash_with_scratch_matches() {
+        let backend = backend::mock::MockBackend {
+            echo_input: true,
+            ..Default::default()
+        };
+        let generator = Generator::from_backend(backend);
+        let pixels: Vec<u8> = (0..64 * 64 * 4).map(|i| (i % 251) as u8).collect();
+        let mut scratch = HashScratch::new();
+
+        for format in [PixelFormat::Bgra, PixelFormat::Rgba, PixelFormat::Abgr] {
+            let options = HashOptions::new().pixel_format(format);
+            let plain = generator.compute_hash(&pixels, 64, 64, options).unwrap();
+            let reused = generator
+                .compute_hash_with_scratch(&pixels, 64, 64, 0, options, &mut scratch)
+                .unwrap();
+            assert_eq!(plain, reused);
+        }
+        assert!(scratch.capacity() >= pixels.len());
+
+        let (y, chroma) = (vec![7u8; 64 * 64], vec![128u8; 32 * 32]);
+        let plain = generator
+            .compute_hash_yuv420p(&y, &chroma, &chroma, 64, 64, HashOptions::new())
+            .unwrap();
+        let reused = generator
+            .compute_hash_yuv420p_with_scratch(
+                &y,
+                &chroma,
+                &chroma,
+                64,
+                64,
+                HashOptions::new(),
+                &mut scratch,
+            )
+            .unwrap();
+        assert_eq!(plain, reused);
+
+        scratch.shrink_to_fit();
+        assert_eq!(scratch.capacity(), 0);
+    }
+
+    #[test]
+    fn test_last_error() {
+        let generator = Generator::from_backend(backend::mock::MockBackend::default());
+        assert_eq!(generator.last_error(), None);
+
+        let generator = Generator::from_backend(backend::mock::MockBackend {
+            error_number: sys::PhotoDna_ErrorImageTooSmall,
+            ..Default::default()
+        });
+        assert_eq!(
+            generator.last_error_code(),
+            sys::PhotoDna_ErrorImageTooSmall
+        );
+        assert_eq!(generator.last_error(), Some(PhotoDnaError::ImageTooSmall));
+    }
+
+    #[test]
+    fn test_compute_hash_raw() {
+        let generator = Generator::from_backend(backend::mock::MockBackend {
+            echo_input: true,
+            ..Default::default()
+        });
+        let pixels: Vec<u8> = (0..64 * 64 * 3).map(|i| (i % 251) as u8).collect();
+        let options = HashOptions::new();
+
+        let expected = generator.compute_hash(&pixels, 64, 64, options).unwrap();
+        // SAFETY: `pixels` outlives the calls and is not modified.
+        unsafe {
+            assert_eq!(
+                generator.compute_hash_raw(pixels.as_ptr(), pixels.len(), 64, 64, 0, options),
+                Ok(expected)
+            );
+            assert!(matches!(
+                generator.compute_hash_raw(pixels.as_ptr(), 100, 64, 64, 0, options),
+                Err(PhotoDnaError::BufferTooSmall { .. })
+            ));
+            assert_eq!(
+                generator.compute_hash_raw(std::ptr::null(), 0, 64, 64, 0, options),
+                Err(PhotoDnaError::BadArgument)
+            );
+        }
+    }
+
+    #[test]
+    fn test_alpha_mode_converts_pixels() {
+        let generator = Generator::from_backend(backend::mock::MockBackend {
+            echo_input: true,
+            ..Default::default()
+        });
+        let first_pixel = |pixel: [u8; 4], options: HashOptions| {
+            let pixels = pixel.repeat(64 * 64);
+            let hash = generator.compute_hash(&pixels, 64, 64, options).unwrap();
+            <[u8; 4]>::try_from(&hash.as_bytes()[..4]).unwrap()
+        };
+
+        let straight = HashOptions::new().pixel_format(PixelFormat::Rgba);
+        assert_eq!(first_pixel([50, 100, 0, 128], straight), [50, 100, 0, 128]);
+        assert_eq!(
+            first_pixel(
+                [50, 100, 0, 128],
+                straight.alpha_mode(AlphaMode::Premultiplied)
+            ),
+            [100, 199, 0, 128]
+        );
+        assert_eq!(
+            first_pixel(
+                [50, 100, 0, 0],
+                straight.alpha_mode(AlphaMode::Premultiplied)
+            ),
+            [0, 0, 0, 0]
+        );
+
+        let premultiplied = HashOptions::new().pixel_format(PixelFormat::RgbaPremultiplied);
+        assert_eq!(
+            first_pixel(
+                [200, 100, 255, 128],
+                premultiplied.alpha_mode(AlphaMode::Premultiplied)
+            ),
+            [200, 100, 255, 128]
+        );
+        assert_eq!(
+            first_pixel(
+                [200, 100, 255, 128],
+                premultiplied.alpha_mode(AlphaMode::Straight)
+            ),
+            [100, 50, 128, 128]
+        );
+
+        // Channels are reordered before alpha is converted.
+        let bgra = HashOptions::new()
+            .pixel_format(PixelFormat::Bgra)
+            .alpha_mode(AlphaMode::Premultiplied);
+        assert_eq!(first_pixel([0, 50, 25, 128], bgra), [50, 100, 0, 128]);
+    }
+
+    #[test]
+    fn test_pad_to_minimum() {
+        let generator = Generator::from_backend(backend::mock::MockBackend {
+            echo_input: true,
+            ..Default::default()
+        });
+        // A 40×60 image whose pixels hold their column number plus one.
+        let pixels: Vec<u8> = (0..60).flat_map(|_| 1..=40).collect();
+        let gray = HashOptions::new().pixel_format(PixelFormat::Gray8);
+        let padded_row = |mode| {
+            let hash = generator
+                .compute_hash(&pixels, 40, 60, gray.pad_to_minimum(mode))
+                .unwrap();
+            // The padded image is 50 pixels wide and every row is alike.
+            assert_eq!(hash.as_bytes()[..50], hash.as_bytes()[50..100]);
+            hash.as_bytes()[..50].to_vec()
+        };
+
+        let middle: Vec<u8> = (1..=40).collect();
+        let expected = |left: [u8; 5], right: [u8; 5]| [&left[..], &middle, &right].concat();
+        assert_eq!(padded_row(PadMode::Constant(7)), expected([7; 5], [7; 5]));
+        assert_eq!(padded_row(PadMode::Edge), expected([1; 5], [40; 5]));
+        assert_eq!(
+            padded_row(PadMode::Reflect),
+            expected([6, 5, 4, 3, 2], [39, 38, 37, 36, 35])
+        );
+
+        // Images that are large enough, and images without the option, are
+        // hashed as given.
+        let unpadded = generator.compute_hash(&pixels, 40, 60, gray).unwrap();
+        assert_eq!(unpadded.as_bytes()[..40], middle);
+        let large: Vec<u8> = (0..60 * 60).map(|i| (i % 60) as u8).collect();
+        assert_eq!(
+            generator
+                .compute_hash(&large, 60, 60, gray.pad_to_minimum(PadMode::Edge))
+                .unwrap(),
+            generator.compute_hash(&large, 60, 60, gray).unwrap()
+        );
+
+        // Reflection repeats as often as needed for tiny images.
+        let tiny = generator
+            .compute_hash(&[1, 2, 3, 4], 2, 2, gray.pad_to_minimum(PadMode::Reflect))
+            .unwrap();
+        assert_eq!(tiny.as_bytes()[..4], [1, 2, 1, 2]);
+        assert!(gray.pad_to_minimum(PadMode::Edge).can_hash_dimensions(2, 2));
+        assert!(!gray.can_hash_dimensions(2, 2));
+    }
+
+    #[test]
+    fn test_gray32_endian_swaps_bytes() {
+        let generator = Generator::from_backend(backend::mock::MockBackend {
+            echo_input: true,
+            ..Default::default()
+        });
+        let pixels = [1u8, 2, 3, 4].repeat(64 * 64);
+        let gray32 = HashOptions::new().pixel_format(PixelFormat::Gray32);
+        let foreign = match Endian::NATIVE {
+            Endian::Little => Endian::Big,
+            Endian::Big => Endian::Little,
+        };
+
+        let hash = |options| generator.compute_hash(&pixels, 64, 64, options).unwrap();
+        assert_eq!(&hash(gray32).as_bytes()[..4], [1, 2, 3, 4]);
+        assert_eq!(hash(gray32.gray32_endian(Endian::NATIVE)), hash(gray32));
+        assert_eq!(
+            &hash(gray32.gray32_endian(foreign)).as_bytes()[..8],
+            [4, 3, 2, 1, 4, 3, 2, 1]
+        );
+
+        // Other formats are unaffected.
+        let rgba = HashOptions::new().pixel_format(PixelFormat::Rgba);
+        assert_eq!(hash(rgba.gray32_endian(foreign)), hash(rgba));
+    }
+
+    #[test]
+    fn test_inflight_counts_concurrent_calls() {
+        let barrier = std::sync::Arc::new(std::sync::Barrier::new(3));
+        let workers: Vec<_> = (0..2)
+            .map(|_| {
+                let barrier = std::sync::Arc::clone(&barrier);
+                std::thread::spawn(move || {
+                    let generator = Generator::from_backend(backend::mock::MockBackend {
+                        hash_barrier: Some(barrier),
+                        ..Default::default()
+                    });
+                    let pixels = vec![0u8; 64 * 64 * 3];
+                    generator
+                        .compute_hash(&pixels, 64, 64, HashOptions::new())
+                        .unwrap();
+                })
+            })
+            .collect();
+
+        let generator = Generator::from_backend(backend::mock::MockBackend::default());
+        // Both workers are now blocked inside the library. Other tests may
+        // be hashing concurrently, so only a lower bound holds.
+        barrier.wait();
+        assert!(generator.inflight() >= 2);
+        barrier.wait();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_compute_hash_dual() {
+        let generator = Generator::from_backend(backend::mock::MockBackend {
+            echo_input: true,
+            ..Default::default()
+        });
+        let pixels: Vec<u8> = (0..64 * 64 * 3).map(|i| (i % 251) as u8).collect();
+
+        let (hash, base64) = generator
+            .compute_hash_dual(&pixels, 64, 64, HashOptions::new())
+            .unwrap();
+        assert_eq!(hash.as_bytes(), &pixels[..HASH_SIZE]);
+        assert_eq!(base64.len(), HASH_SIZE_MAX);
+        assert_eq!(Hash::from_base64(&base64), Some(hash));
+
+        assert!(generator
+            .compute_hash_dual(&pixels, 100, 100, HashOptions::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_validate_hash() {
+        let generator = Generator::from_backend(backend::mock::MockBackend::default());
+        let pixels = vec![0u8; 64 * 64 * 3];
+        let good = generator
+            .compute_hash(&pixels, 64, 64, HashOptions::new())
+            .unwrap();
+        assert_eq!(generator.validate_hash(&good), Ok(()));
+
+        let partial = Hash::from_slice(&good.as_bytes()[..100]).unwrap();
+        let text = Hash::from_slice(&good.to_base64().as_bytes()[..HASH_SIZE]).unwrap();
+        for garbage in [partial, text, Hash::from_slice(&[]).unwrap()] {
+            assert_eq!(
+                generator.validate_hash(&garbage),
+                Err(PhotoDnaError::InvalidHash)
+            );
+        }
+    }
+
+    #[test]
+    fn test_compute_hash_with_retry() {
+        let pixels = vec![0u8; 64 * 64 * 3];
+        let no_wait = Duration::ZERO;
+
+        let backend = backend::mock::MockBackend::default();
+        backend.transient_failures.store(2, Ordering::Relaxed);
+        let calls = backend.calls.clone();
+        let generator = Generator::from_backend(backend);
+        let hash = generator
+            .compute_hash_with_retry(&pixels, 64, 64, HashOptions::new(), 2, no_wait)
+            .unwrap();
+        assert_eq!(hash.as_bytes()[0], 0xAB);
+        assert_eq!(calls.lock().unwrap().len(), 3);
+
+        let backend = backend::mock::MockBackend::default();
+        backend.transient_failures.store(3, Ordering::Relaxed);
+        let generator = Generator::from_backend(backend);
+        assert_eq!(
+            generator.compute_hash_with_retry(&pixels, 64, 64, HashOptions::new(), 2, no_wait),
+            Err(PhotoDnaError::LibraryFailure)
+        );
+
+        let backend = backend::mock::MockBackend {
+            hash_return: sys::PhotoDna_ErrorImageIsFlat,
+            ..Default::default()
+        };
+        let calls = backend.calls.clone();
+        let generator = Generator::from_backend(backend);
+        assert_eq!(
+            generator.compute_hash_with_retry(&pixels, 64, 64, HashOptions::new(), 5, no_wait),
+            Err(PhotoDnaError::ImageIsFlat)
+        );
+        assert_eq!(calls.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_compute_hash_resilient() {
+        let pixels = vec![0u8; 64 * 64 * 3];
+
+        // The first instance fails; the reloaded one hashes normally.
+        let backend = backend::mock::MockBackend::default();
+        backend.transient_failures.store(1, Ordering::Relaxed);
+        let calls = backend.calls.clone();
+        let reloads = std::sync::Arc::new(AtomicU32::new(0));
+        let counter = reloads.clone();
+        let mut generator = Generator::from_backend(backend).with_loader(move || {
+            counter.fetch_add(1, Ordering::Relaxed);
+            Ok(Box::new(backend::mock::MockBackend::default()) as Box<dyn Backend>)
+        });
+        let hash = generator
+            .compute_hash_resilient(&pixels, 64, 64, HashOptions::new())
+            .unwrap();
+        assert_eq!(hash.as_bytes()[0], 0xAB);
+        assert_eq!(reloads.load(Ordering::Relaxed), 1);
+        assert_eq!(calls.lock().unwrap().len(), 1);
+
+        // The reloaded instance is kept and used without reloading again.
+        generator
+            .compute_hash_resilient(&pixels, 64, 64, HashOptions::new())
+            .unwrap();
+        assert_eq!(reloads.load(Ordering::Relaxed), 1);
+
+        // Errors other than `LibraryFailure` do not reload.
+        assert_eq!(
+            generator.compute_hash_resilient(&pixels, 100, 100, HashOptions::new()),
+            Err(PhotoDnaError::BufferTooSmall {
+                expected: 100 * 100 * 3,
+                actual: pixels.len(),
+            })
+        );
+        assert_eq!(reloads.load(Ordering::Relaxed), 1);
+
+        // A failed reload is reported and the old instance is kept.
+        let backend = backend::mock::MockBackend::default();
+        backend.transient_failures.store(2, Ordering::Relaxed);
+        let mut generator = Generator::from_backend(backend)
+            .with_loader(|| Err(PhotoDnaError::UnsupportedPlatform));
+        assert_eq!(
+            generator.compute_hash_resilient(&pixels, 64, 64, HashOptions::new()),
+            Err(PhotoDnaError::UnsupportedPlatform)
+        );
+        assert_eq!(
+            generator.compute_hash_resilient(&pixels, 64, 64, HashOptions::new()),
+            Err(PhotoDnaError::UnsupportedPlatform)
+        );
+        assert!(generator
+            .compute_hash_resilient(&pixels, 64, 64, HashOptions::new())
+            .is_ok());
+    }
+
+    #[test]
+    fn test_skip_buffer_validation() {
+        let backend = backend::mock::MockBackend::default();
+        let calls = backend.calls.clone();
+        let generator = Generator::from_backend(backend);
+        let short = vec![0u8; 16];
+
+        assert!(matches!(
+            generator.compute_hash(&short, 64, 64, HashOptions::new()),
+            Err(PhotoDnaError::BufferTooSmall { .. })
+        ));
+        assert!(calls.lock().unwrap().is_empty());
+
+        // SAFETY: the mock never reads the image.
+        let options = unsafe { HashOptions::new().skip_buffer_validation(true) };
+        let hash = generator.compute_hash(&short, 64, 64, options).unwrap();
+        assert_eq!(hash.as_bytes()[0], 0xAB);
+        assert_eq!(*calls.lock().unwrap(), ["edge_hash"]);
+    }
+
+    #[test]
+    fn test_scratch_pool_keeps_conversion_buffers() {
+        let backend = backend::mock::MockBackend {
+            echo_input: true,
+            ..Default::default()
+        };
+        let mut generator = Generator::from_backend(backend);
+        generator.scratch = ScratchPool::new(2);
+        let pixels: Vec<u8> = (0..64 * 64 * 3).map(|i| (i % 251) as u8).collect();
+        let options = HashOptions::new().pixel_format(PixelFormat::Bgr);
+
+        let first = generator.compute_hash(&pixels, 64, 64, options).unwrap();
+        assert_eq!(generator.scratch.idle(), 1);
+        let second = generator.compute_hash(&pixels, 64, 64, options).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(generator.scratch.idle(), 1);
+
+        let (y, chroma) = (vec![7u8; 64 * 64], vec![128u8; 32 * 32]);
+        generator
+            .compute_hash_yuv420p(&y, &chroma, &chroma, 64, 64, HashOptions::new())
+            .unwrap();
+        assert_eq!(generator.scratch.idle(), 1);
+    }
+
+    #[test]
+    fn test_region_grid_covers_image() {
+        let tiles = Region::grid(101, 50, 2, 2);
+        assert_eq!(
+            tiles,
+            [
+                Region::from_xywh(0, 0, 50, 25),
+                Region::from_xywh(50, 0, 51, 25),
+                Region::from_xywh(0, 25, 50, 25),
+                Region::from_xywh(50, 25, 51, 25),
+            ]
+        );
+        assert!(Region::grid(100, 100, 0, 2).is_empty());
+    }
+
+    #[test]
+    fn test_compute_tile_hashes_with_border() {
+        // A 2x2 collage of framed gradients, 60x60 pixels per cell.
+        let (width, height) = (120u32, 120u32);
+        let mut pixels = vec![0u8; (width * height * 3) as usize];
+        for y in 0..height {
+            for x in 0..width {
+                let (cx, cy) = (x % 60, y % 60);
+                let framed = cx < 5 || cy < 5 || cx >= 55 || cy >= 55;
+                let value = if framed { 255 } else { (cx * 4 + cy) as u8 };
+                let i = ((y * width + x) * 3) as usize;
+                pixels[i..i + 3].fill(value);
+            }
+        }
+
+        let backend = backend::mock::MockBackend::default();
+        let calls = backend.calls.clone();
+        let generator = Generator::from_backend(backend);
+
+        let mut tiles = Region::grid(width, height, 2, 2);
+        tiles.push(Region::from_xywh(100, 100, 60, 60));
+        let results = generator
+            .compute_tile_hashes_with_border(&pixels, width, height, 0, &tiles, HashOptions::new())
+            .unwrap();
+
+        assert_eq!(results.len(), 5);
+        for result in &results[..4] {
+            let result = result.as_ref().unwrap();
+            assert_eq!(result.primary.as_bytes()[0], 0xAB);
+        }
+        assert!(matches!(results[4], Err(PhotoDnaError::InvalidSubImage)));
+        assert_eq!(
+            calls
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|&&c| c == "edge_hash_border_sub")
+                .count(),
+            4
+        );
+
+        assert!(matches!(
+            generator.compute_tile_hashes_with_border(
+                &pixels[..10],
+                width,
+                height,
+                0,
+                &tiles,
+                HashOptions::new()
+            ),
+            Err(PhotoDnaError::BufferTooSmall { .. })
+        ));
+    }
+
+    #[test]
+    fn test_compute_hash_windows() {
+        let pixels = vec![7u8; 300 * 100 * 3];
+        let backend = backend::mock::MockBackend::default();
+        let regions_seen = backend.regions.clone();
+        let generator = Generator::from_backend(backend);
+
+        let windows =
+            generator.compute_hash_windows(&pixels, 300, 100, 0, 100, 50, HashOptions::new());
+
+        // The window at x=250 is clipped to 50px and still hashed.
+        let expected: Vec<Region> = [0, 50, 100, 150, 200, 250]
+            .into_iter()
+            .map(|x| Region::from_xywh(x, 0, 100.min(300 - x), 100))
+            .collect();
+        let regions: Vec<Region> = windows.iter().map(|(region, _)| *region).collect();
+        assert_eq!(regions, expected);
+        assert!(windows.iter().all(|(_, result)| result.is_ok()));
+        assert_eq!(*regions_seen.lock().unwrap(), expected);
+
+        // The 20px remainder of a 70px step is skipped.
+        let windows =
+            generator.compute_hash_windows(&pixels, 300, 100, 0, 100, 70, HashOptions::new());
+        let starts: Vec<u32> = windows.iter().map(|(region, _)| region.x).collect();
+        assert_eq!(starts, [0, 70, 140, 210]);
+        assert_eq!(windows[3].0.width, 90);
+    }
+
+    #[test]
+    fn test_compute_hashes_for_regions() {
+        let (width, height) = (120u32, 100u32);
+        let pixels = vec![7u8; (width * height * 3) as usize];
+
+        let backend = backend::mock::MockBackend::default();
+        let regions_seen = backend.regions.clone();
+        let generator = Generator::from_backend(backend);
+
+        let regions = [
+            Region::from_xywh(0, 0, 80, 80),
+            Region::from_xywh(40, 20, 80, 80),
+            Region::from_xywh(100, 0, 50, 50),
+            Region::from_xywh(0, 0, 60, 40),
+        ];
+        let results = generator.compute_hashes_for_regions(
+            &pixels,
+            width,
+            height,
+            0,
+            &regions,
+            HashOptions::new(),
+        );
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().unwrap().as_bytes()[0], 0xAB);
+        assert_eq!(results[1].as_ref().unwrap().as_bytes()[0], 0xAB);
+        assert_eq!(results[2], Err(PhotoDnaError::InvalidSubImage));
+        assert_eq!(
+            results[3],
+            Err(PhotoDnaError::InvalidDimensions {
+                width: 60,
+                height: 40
+            })
+        );
+        // Only the two overlapping regions reached the library, unchanged.
+        assert_eq!(*regions_seen.lock().unwrap(), regions[..2]);
+
+        let results = generator.compute_hashes_for_regions(
+            &pixels[..10],
+            width,
+            height,
+            0,
+            &regions[..2],
+            HashOptions::new(),
+        );
+        assert!(results
+            .iter()
+            .all(|r| matches!(r, Err(PhotoDnaError::BufferTooSmall { .. }))));
+        assert_eq!(regions_seen.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_check_memory_first_n() {
+        let backend = backend::mock::MockBackend::default();
+        let seen = backend.options.clone();
+        let mut generator = Generator::from_backend(backend);
+        generator.check_memory_calls = AtomicU32::new(2);
+
+        let pixels = vec![0u8; 60 * 60 * 3];
+        for _ in 0..3 {
+            generator.compute_hash_rgb(&pixels, 60, 60).unwrap();
+        }
+        // A call rejected before reaching the library does not count.
+        assert!(generator.compute_hash_rgb(&pixels[..10], 60, 60).is_err());
+
+        let checked: Vec<bool> = seen
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|&flags| flags & sys::PhotoDna_CheckMemory != 0)
+            .collect();
+        assert_eq!(checked, vec![true, true, false]);
+    }
+
+    #[test]
+    fn test_channel_orders_hash_consistently() {
+        fn generator() -> Generator {
+            Generator::from_backend(backend::mock::MockBackend {
+                echo_input: true,
+                ..Default::default()
+            })
+        }
+
+        // 60x60 RGBA gradient, with 8 padding bytes per row.
+        let (width, height, stride) = (60u32, 60u32, 60 * 4 + 8);
+        let mut rgba = vec![0xEE; stride as usize * height as usize];
+        for (y, row) in rgba.chunks_mut(stride as usize).enumerate() {
+            for (x, pixel) in row[..240].chunks_exact_mut(4).enumerate() {
+                pixel.copy_from_slice(&[x as u8, y as u8, (x + y) as u8, 200]);
+            }
+        }
+        let convert = |order: &[usize]| -> Vec<u8> {
+            let mut out = rgba.clone();
+            for (src, dst) in rgba
+                .chunks(stride as usize)
+                .zip(out.chunks_mut(stride as usize))
+            {
+                for (s, d) in src[..240]
+                    .chunks_exact(4)
+                    .zip(dst.chunks_exact_mut(order.len()))
+                {
+                    for (d, &i) in d.iter_mut().zip(order) {
+                        *d = s[i];
+                    }
+                }
+            }
+            out
+        };
+
+        let hash = |data: &[u8], order: ChannelOrder| {
+            let stride = if order.channels() == 4 { stride } else { 0 };
+            generator()
+                .compute_hash_with_stride(
+                    data,
+                    width,
+                    height,
+                    stride,
+                    HashOptions::new().input_channel_order(order),
+                )
+                .unwrap()
+        };
+
+        let packed_rgb: Vec<u8> = rgba
+            .chunks(stride as usize)
+            .flat_map(|row| row[..240].chunks_exact(4).flat_map(|p| &p[..3]))
+            .copied()
+            .collect();
+        let packed_bgr: Vec<u8> = packed_rgb
+            .chunks_exact(3)
+            .flat_map(|p| [p[2], p[1], p[0]])
+            .collect();
+
+        assert_eq!(
+            hash(&packed_bgr, ChannelOrder::Bgr),
+            hash(&packed_rgb, ChannelOrder::Rgb)
+        );
+        assert_eq!(
+            hash(&convert(&[2, 1, 0, 3]), ChannelOrder::Bgra),
+            hash(&rgba, ChannelOrder::Rgba)
+        );
+        assert_eq!(
+            hash(&convert(&[3, 2, 1, 0]), ChannelOrder::Abgr),
+            hash(&convert(&[3, 0, 1, 2]), ChannelOrder::Argb)
+        );
+        // The library really receives different bytes without reordering.
+        assert_ne!(
+            hash(&packed_bgr, ChannelOrder::Rgb),
+            hash(&packed_rgb, ChannelOrder::Rgb)
+        );
+    }
+
+    #[test]
+    fn test_verify() {
+        let generator = Generator::from_backend(backend::mock::MockBackend::default());
+        assert_eq!(generator.verify(), Ok(()));
+
+        let generator = Generator::from_backend(backend::mock::MockBackend {
+            fill_byte: 0,
+            ..Default::default()
+        });
+        assert!(matches!(
+            generator.verify(),
+            Err(PhotoDnaError::VerificationFailed(reason)) if reason.contains("zeros")
+        ));
+
+        let generator = Generator::from_backend(backend::mock::MockBackend {
+            hash_return: sys::PhotoDna_ErrorImageIsFlat,
+            ..Default::default()
+        });
+        assert!(matches!(
+            generator.verify(),
+            Err(PhotoDnaError::VerificationFailed(reason)) if reason.contains("flat")
+        ));
+    }
+
+    #[test]
+    fn test_verify_compares_recorded_hash_for_matching_version() {
+        let generator = Generator::from_backend(backend::mock::MockBackend::default());
+        assert_eq!(
+            generator.verify_against(Some(("mock", [0xAB; HASH_SIZE]))),
+            Ok(())
+        );
+        assert!(matches!(
+            generator.verify_against(Some(("mock", [0xCD; HASH_SIZE]))),
+            Err(PhotoDnaError::VerificationFailed(reason)) if reason.contains("recorded")
+        ));
+        // A hash recorded with another library version is not compared.
+        assert_eq!(
+            generator.verify_against(Some(("1.0", [0xCD; HASH_SIZE]))),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_hash_corpus_reports_progress() {
+        let generator = Generator::from_backend(backend::mock::MockBackend::default());
+        let pixels = vec![0u8; 60 * 60 * 3];
+        let images = vec![
+            ImageRef::new(&pixels, 60, 60),
+            ImageRef::new(&pixels[..10], 60, 60),
+            ImageRef::new(&pixels, 60, 60).with_stride(180),
+        ];
+
+        let mut progress = Vec::new();
+        let results = generator.hash_corpus(images, HashOptions::new(), |done, total| {
+            progress.push((done, total))
+        });
+
+        assert_eq!(progress, vec![(1, 3), (2, 3), (3, 3)]);
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(matches!(
+            results[1],
+            Err(PhotoDnaError::BufferTooSmall { .. })
+        ));
+        assert!(results[2].is_ok());
+
+        // Unsized iterators report a total of 0.
+        let mut calls = 0;
+        let unsized_iter = (0..2)
+            .map(|_| ImageRef::new(&pixels, 60, 60))
+            .filter(|_| true);
+        generator.hash_corpus(unsized_iter, HashOptions::new(), |_, total| {
+            assert_eq!(total, 0);
+            calls += 1;
+        });
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn test_compute_many() {
+        let generator = Generator::from_backend(backend::mock::MockBackend {
+            echo_input: true,
+            ..Default::default()
+        });
+        let small = vec![1u8; 60 * 60 * 3];
+        let large = vec![2u8; 80 * 70 * 3];
+
+        let results = generator.compute_many(
+            [
+                (small.as_slice(), 60, 60),
+                (&small[..10], 60, 60),
+                (large.as_slice(), 80, 70),
+            ],
+            HashOptions::new(),
+        );
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().as_bytes()[0], 1);
+        assert!(matches!(
+            results[1],
+            Err(PhotoDnaError::BufferTooSmall { .. })
+        ));
+        assert_eq!(results[2].as_ref().unwrap().as_bytes()[0], 2);
+    }
+
+    #[test]
+    fn test_max_pixels() {
+        let mut generator = Generator::from_backend(backend::mock::MockBackend::default());
+        generator.max_pixels = 60 * 60;
+        let pixels = vec![0u8; 61 * 60 * 3];
+        let too_large = |result: Result<Hash>| {
+            matches!(
+                result,
+                Err(PhotoDnaError::ImageTooLarge {
+                    max_pixels: 3600,
+                    ..
+                })
+            )
+        };
+
+        assert!(generator
+            .compute_hash(&pixels, 60, 60, HashOptions::new())
+            .is_ok());
+        assert!(too_large(generator.compute_hash(
+            &pixels,
+            61,
+            60,
+            HashOptions::new()
+        )));
+        // Claimed dimensions are rejected before the buffer is examined.
+        assert!(too_large(generator.compute_hash(
+            &pixels,
+            100_000,
+            100_000,
+            HashOptions::new()
+        )));
+        assert!(too_large(generator.compute_hash_subregion(
+            &pixels,
+            61,
+            60,
+            0,
+            Region::from_xywh(0, 0, 50, 50),
+            HashOptions::new()
+        )));
+        assert!(too_large(generator.compute_hash_yuv420p(
+            &pixels,
+            &pixels,
+            &pixels,
+            61,
+            60,
+            HashOptions::new()
+        )));
+
+        let mut rows = generator.row_hasher(61, 60, 0, HashOptions::new());
+        assert!(matches!(
+            rows.push_row(&pixels[..61 * 3]),
+            Err(PhotoDnaError::ImageTooLarge { .. })
+        ));
+        assert_eq!(GeneratorOptions::new().max_pixels, DEFAULT_MAX_PIXELS);
+    }
+
+    #[test]
+    #[cfg(feature = "catch-ffi")]
+    fn test_catch_ffi_converts_panics() {
+        let generator = Generator::from_backend(backend::mock::MockBackend {
+            panic_on_hash: true,
+            ..Default::default()
+        });
+        let data = vec![0u8; 60 * 60 * 3];
+
+        assert_eq!(
+            generator.compute_hash_rgb(&data, 60, 60),
+            Err(PhotoDnaError::LibraryFailure)
+        );
+        assert!(matches!(
+            generator.compute_hash_with_border_detection(&data, 60, 60, HashOptions::new()),
+            Err(PhotoDnaError::LibraryFailure)
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "stub")]
+    fn test_stub_generator_returns_fixture() {
+        let generator = Generator::new_stub();
+        let image = vec![0u8; 100 * 100 * 3];
+
+        let hash = generator.compute_hash_rgb(&image, 100, 100).unwrap();
+        assert_eq!(hash, test_utils::fixtures::sample_hash_a());
+        assert!(generator
+            .library_version_text()
+            .unwrap()
+            .contains("not PhotoDNA"));
+
+        // Validation still applies to stub generators.
+        assert!(matches!(
+            generator.compute_hash_rgb(&image[..10], 100, 100),
+            Err(PhotoDnaError::BufferTooSmall { .. })
+        ));
+    }
+
+    #[cfg(feature = "stub")]
+    #[test]
+    fn test_content_stub_depends_on_pixels() {
+        let generator = Generator::new_content_stub();
+        let image: Vec<u8> = (0..100 * 100 * 3).map(|i| (i % 251) as u8).collect();
+        let hash = generator.compute_hash_rgb(&image, 100, 100).unwrap();
+
+        assert!(hash.is_full());
+        assert_eq!(generator.compute_hash_rgb(&image, 100, 100).unwrap(), hash);
+        assert_eq!(
+            Generator::new_content_stub()
+                .compute_hash_rgb(&image, 100, 100)
+                .unwrap(),
+            hash
+        );
+
+        let mut changed = image.clone();
+        changed[5000] ^= 1;
+        assert_ne!(
+            generator.compute_hash_rgb(&changed, 100, 100).unwrap(),
+            hash
+        );
+        assert_ne!(generator.compute_hash_rgb(&image, 50, 200).unwrap(), hash);
+
+        // Row padding is not part of the image.
+        let padded: Vec<u8> = image
+            .chunks(300)
+            .flat_map(|row| row.iter().copied().chain([9; 12]))
+            .collect();
+        assert_eq!(
+            generator
+                .compute_hash_with_stride(&padded, 100, 100, 312, HashOptions::new())
+                .unwrap(),
+            hash
+        );
+
+        // A sub-region hashes like the same pixels cut out.
+        let region = Region::from_xywh(10, 20, 60, 50);
+        let cropped: Vec<u8> = image
+            .chunks(300)
+            .skip(20)
+            .take(50)
+            .flat_map(|row| row[30..210].iter().copied())
+            .collect();
+        assert_eq!(
+            generator
+                .compute_hash_subregion(&image, 100, 100, 0, region, HashOptions::new())
+                .unwrap(),
+            generator.compute_hash_rgb(&cropped, 60, 50).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_dimensions_beyond_i32_are_rejected() {
+        let over = i32::MAX as u32 + 1;
+        assert_eq!(
+            checked_dimensions(over, 100),
+            Err(PhotoDnaError::InvalidDimensions {
+                width: over,
+                height: 100
+            })
+        );
+        assert!(checked_dimensions(100, over).is_err());
+        assert!(checked_dimensions(0, 100).is_err());
+        assert_eq!(checked_dimensions(i32::MAX as u32, 1), Ok((i32::MAX, 1)));
+        assert_eq!(checked_stride(over), Err(PhotoDnaError::InvalidStride));
+        assert_eq!(checked_stride(i32::MAX as u32), Ok(i32::MAX));
+
+        let generator = Generator::from_backend(backend::mock::MockBackend::default());
+        let data = [0u8; 16];
+        assert!(matches!(
+            generator.compute_hash_with_stride(&data, over, 1, 0, HashOptions::new()),
+            Err(PhotoDnaError::InvalidDimensions { .. })
+        ));
+        assert_eq!(
+            generator.compute_hash_with_stride(&data, 1, 1, over, HashOptions::new()),
+            Err(PhotoDnaError::InvalidStride)
+        );
+    }
+
+    #[test]
+    fn test_generator_options_single_threaded() {
+        let options = GeneratorOptions::new().max_threads(8).single_threaded();
+        assert_eq!(options.max_threads, 1);
+    }
+
     #[test]
     fn test_generator_options_max_threads_minimum() {
         let options = GeneratorOptions::new().max_threads(-5);
@@ -0,0 +1,229 @@
+//! Incremental hashing of images delivered one row at a time.
+//!
+//! Streaming decoders often produce scanlines one by one. [`RowHasher`]
+//! collects them into the layout the library expects and hashes the image
+//! once the last row arrives.
+
+use crate::{Generator, Hash, HashOptions, PhotoDnaError, Result};
+
+/// Collects image rows and hashes them once the image is complete.
+///
+/// Created by [`Generator::row_hasher`]. Rows are buffered internally, so
+/// callers don't need to assemble the full image themselves, and short
+/// rows are rejected as they are pushed rather than when hashing.
+///
+/// Planar formats such as [`PixelFormat::Yuv420p`](crate::PixelFormat::Yuv420p)
+/// are not row-oriented and cannot be hashed this way.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let mut hasher = generator.row_hasher(width, height, 0, HashOptions::default());
+/// for row in decoder.rows() {
+///     hasher.push_row(row?)?;
+/// }
+/// let hash = hasher.finish()?;
+/// ```
+pub struct RowHasher<'g> {
+    generator: &'g Generator,
+    width: u32,
+    height: u32,
+    stride: u32,
+    options: HashOptions,
+    /// Bytes of pixel data in each row.
+    pixel_bytes: usize,
+    /// Bytes each row occupies in `buffer`, including padding.
+    row_len: usize,
+    buffer: Vec<u8>,
+    rows: u32,
+}
+
+impl Generator {
+    /// Starts hashing an image that will be supplied row by row.
+    ///
+    /// `stride` is the row stride in bytes, or 0 for tightly packed rows,
+    /// exactly as for [`compute_hash_with_stride`](Self::compute_hash_with_stride).
+    pub fn row_hasher(
+        &self,
+        width: u32,
+        height: u32,
+        stride: u32,
+        options: HashOptions,
+    ) -> RowHasher<'_> {
+        let pixel_bytes = (width as usize).saturating_mul(options.pixel_format.bytes_per_pixel());
+        let row_len = if stride == 0 {
+            pixel_bytes
+        } else {
+            stride as usize
+        };
+
+        RowHasher {
+            generator: self,
+            width,
+            height,
+            stride,
+            options,
+            pixel_bytes,
+            row_len,
+            buffer: Vec::new(),
+            rows: 0,
+        }
+    }
+}
+
+impl RowHasher<'_> {
+    /// Appends the next row of the image.
+    ///
+    /// The row must hold at least `width` pixels. With an explicit stride,
+    /// bytes past the stride are ignored and missing padding is zero-filled.
+    ///
+    /// # Errors
+    ///
+    /// - [`PhotoDnaError::BufferTooSmall`] if the row is shorter than
+    ///   `width` pixels.
+    /// - [`PhotoDnaError::RowCount`] if all `height` rows were already
+    ///   pushed.
+    /// - [`PhotoDnaError::ImageTooLarge`] if the image exceeds the
+    ///   generator's [pixel limit](crate::GeneratorOptions::max_pixels).
+    pub fn push_row(&mut self, row: &[u8]) -> Result<()> {
+        if self.rows == self.height {
+            return Err(PhotoDnaError::RowCount {
+                expected: self.height,
+                actual: self.rows.saturating_add(1),
+            });
+        }
+        if row.len() < self.pixel_bytes {
+            return Err(PhotoDnaError::BufferTooSmall {
+                expected: self.pixel_bytes,
+                actual: row.len(),
+            });
+        }
+
+        if self.buffer.is_empty() {
+            self.generator.check_pixels(self.width, self.height)?;
+            self.buffer
+                .reserve(self.row_len.saturating_mul(self.height as usize));
+        }
+        let row = &row[..row.len().min(self.row_len)];
+        self.buffer.extend_from_slice(row);
+        self.buffer
+            .resize(self.buffer.len() + (self.row_len - row.len()), 0);
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Returns the number of rows pushed so far.
+    #[inline]
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    /// Hashes the completed image.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PhotoDnaError::RowCount`] if fewer than `height` rows were
+    /// pushed, or any error from
+    /// [`compute_hash_with_stride`](Generator::compute_hash_with_stride).
+    pub fn finish(self) -> Result<Hash> {
+        if self.rows != self.height {
+            return Err(PhotoDnaError::RowCount {
+                expected: self.height,
+                actual: self.rows,
+            });
+        }
+        self.generator.compute_hash_with_stride(
+            &self.buffer,
+            self.width,
+            self.height,
+            self.stride,
+            self.options,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::mock::MockBackend;
+
+    fn echo_generator() -> Generator {
+        Generator::from_backend(MockBackend {
+            echo_input: true,
+            ..Default::default()
+        })
+    }
+
+    fn gradient(width: usize, height: usize) -> Vec<u8> {
+        (0..width * height * 3)
+            .map(|i| ((i / 3) % width + (i / 3) / width * 2) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn test_push_rows_matches_whole_image() {
+        let generator = echo_generator();
+        let pixels = gradient(64, 64);
+
+        let mut hasher = generator.row_hasher(64, 64, 0, HashOptions::new());
+        for row in pixels.chunks(64 * 3) {
+            hasher.push_row(row).unwrap();
+        }
+        assert_eq!(hasher.rows(), 64);
+
+        let streamed = hasher.finish().unwrap();
+        let whole = generator
+            .compute_hash(&pixels, 64, 64, HashOptions::new())
+            .unwrap();
+        assert_eq!(streamed, whole);
+    }
+
+    #[test]
+    fn test_push_row_pads_to_stride() {
+        let generator = echo_generator();
+        let pixels = gradient(64, 64);
+
+        let mut hasher = generator.row_hasher(64, 64, 200, HashOptions::new());
+        for row in pixels.chunks(64 * 3) {
+            hasher.push_row(row).unwrap();
+        }
+        let hash = hasher.finish().unwrap();
+        assert_eq!(&hash.as_bytes()[..192], &pixels[..192]);
+        assert_eq!(&hash.as_bytes()[192..200], &[0; 8]);
+        assert_eq!(&hash.as_bytes()[200..392], &pixels[192..384]);
+    }
+
+    #[test]
+    fn test_row_count_and_length_errors() {
+        let generator = echo_generator();
+        let row = [0u8; 64 * 3];
+
+        let mut hasher = generator.row_hasher(64, 2, 0, HashOptions::new());
+        assert!(matches!(
+            hasher.push_row(&row[..100]),
+            Err(PhotoDnaError::BufferTooSmall {
+                expected: 192,
+                actual: 100
+            })
+        ));
+        hasher.push_row(&row).unwrap();
+        hasher.push_row(&row).unwrap();
+        assert!(matches!(
+            hasher.push_row(&row),
+            Err(PhotoDnaError::RowCount {
+                expected: 2,
+                actual: 3
+            })
+        ));
+
+        let mut short = generator.row_hasher(64, 64, 0, HashOptions::new());
+        short.push_row(&row).unwrap();
+        assert!(matches!(
+            short.finish(),
+            Err(PhotoDnaError::RowCount {
+                expected: 64,
+                actual: 1
+            })
+        ));
+    }
+}
@@ -0,0 +1,108 @@
+//! Reusable conversion buffers shared by a generator's hashing calls.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::{Mutex, PoisonError};
+
+/// A bounded pool of idle scratch buffers.
+///
+/// With a size of 0 the pool never retains a buffer, so every call
+/// allocates as if there were no pool.
+#[derive(Debug, Default)]
+pub(crate) struct ScratchPool {
+    /// Idle buffers, at most `size` of them.
+    idle: Mutex<Vec<Vec<u8>>>,
+    /// Largest number of idle buffers kept.
+    size: usize,
+}
+
+impl ScratchPool {
+    /// Creates a pool that keeps up to `size` idle buffers.
+    pub(crate) fn new(size: usize) -> Self {
+        Self {
+            idle: Mutex::new(Vec::with_capacity(size)),
+            size,
+        }
+    }
+
+    /// Takes an idle buffer, or an empty one if none is available.
+    ///
+    /// The buffer returns to the pool when the guard is dropped.
+    pub(crate) fn take(&self) -> PooledScratch<'_> {
+        let buffer = if self.size == 0 {
+            Vec::new()
+        } else {
+            self.lock().pop().unwrap_or_default()
+        };
+        PooledScratch { pool: self, buffer }
+    }
+
+    /// Returns the number of idle buffers.
+    #[cfg(test)]
+    pub(crate) fn idle(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Vec<u8>>> {
+        // The buffers hold no invariants a panicking call could break.
+        self.idle.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A scratch buffer on loan from a [`ScratchPool`].
+pub(crate) struct PooledScratch<'p> {
+    pool: &'p ScratchPool,
+    buffer: Vec<u8>,
+}
+
+impl Deref for PooledScratch<'_> {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.buffer
+    }
+}
+
+impl DerefMut for PooledScratch<'_> {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buffer
+    }
+}
+
+impl Drop for PooledScratch<'_> {
+    fn drop(&mut self) {
+        // Buffers that never grew are not worth keeping.
+        if self.pool.size == 0 || self.buffer.capacity() == 0 {
+            return;
+        }
+        let mut idle = self.pool.lock();
+        if idle.len() < self.pool.size {
+            idle.push(std::mem::take(&mut self.buffer));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_reuses_up_to_size_buffers() {
+        let pool = ScratchPool::new(1);
+        {
+            let mut a = pool.take();
+            let mut b = pool.take();
+            a.resize(100, 0);
+            b.resize(10, 0);
+        }
+        assert_eq!(pool.idle(), 1);
+
+        let reused = pool.take();
+        assert!(reused.capacity() >= 10);
+        assert_eq!(pool.idle(), 0);
+        drop(reused);
+
+        let unpooled = ScratchPool::new(0);
+        unpooled.take().resize(100, 0);
+        assert_eq!(unpooled.idle(), 0);
+    }
+}
@@ -0,0 +1,343 @@
+//! Serde support for hashes, stores and hashing options (requires the
+//! `serde` feature).
+//!
+//! Human-readable formats such as JSON and YAML get hashes as lowercase
+//! hex strings; binary formats get the raw bytes. A [`HashStore`] is a map
+//! from id to hash, in insertion order. A [`PixelFormat`] is its
+//! [name](PixelFormat::name), and [`HashOptions`] a struct of the pixel
+//! format and flags, so hashing configuration can live in a config file.
+
+use crate::{Hash, HashOptions, HashStore, PixelFormat, HASH_SIZE};
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeMap, SerializeStruct};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl Serialize for Hash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_hex())
+        } else {
+            serializer.serialize_bytes(self.as_bytes())
+        }
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<'de> Deserialize<'de> for Hash {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            // Hex strings normally, but byte arrays are accepted too.
+            deserializer.deserialize_any(HashVisitor)
+        } else {
+            deserializer.deserialize_bytes(HashVisitor)
+        }
+    }
+}
+
+/// Accepts a hex string, a byte buffer, or a sequence of bytes.
+struct HashVisitor;
+
+impl<'de> Visitor<'de> for HashVisitor {
+    type Value = Hash;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a hex string or up to {} bytes", HASH_SIZE)
+    }
+
+    fn visit_str<E: de::Error>(self, hex: &str) -> Result<Hash, E> {
+        Hash::from_hex(hex).ok_or_else(|| E::invalid_value(de::Unexpected::Str(hex), &self))
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Hash, E> {
+        Hash::from_slice(bytes).ok_or_else(|| E::invalid_length(bytes.len(), &self))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Hash, A::Error> {
+        let mut hash = Hash::zeroed();
+        let mut len = 0;
+        while let Some(byte) = seq.next_element::<u8>()? {
+            if len == HASH_SIZE {
+                return Err(de::Error::invalid_length(len + 1, &self));
+            }
+            hash.as_mut_bytes()[len] = byte;
+            len += 1;
+        }
+        hash.set_len(len);
+        Ok(hash)
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl Serialize for HashStore {
+    /// Serializes the store as a map from id to hash, in insertion order.
+    ///
+    /// The prefilter setting is not serialized.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (id, hash) in self.iter() {
+            map.serialize_entry(id, hash)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<'de> Deserialize<'de> for HashStore {
+    /// Deserializes a map from id to hash into an unfiltered store.
+    ///
+    /// A repeated id is an error.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(StoreVisitor)
+    }
+}
+
+/// Builds a store from a map of id to hash.
+struct StoreVisitor;
+
+impl<'de> Visitor<'de> for StoreVisitor {
+    type Value = HashStore;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a map from id to hash")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<HashStore, A::Error> {
+        let mut store = HashStore::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some((id, hash)) = map.next_entry::<String, Hash>()? {
+            if store.get(&id).is_some() {
+                return Err(de::Error::custom(format_args!("duplicate id `{}`", id)));
+            }
+            store.insert(id, hash);
+        }
+        Ok(store)
+    }
+}
+
+/// Every [`PixelFormat::name`], for error messages.
+const PIXEL_FORMAT_NAMES: [&str; PixelFormat::all().len()] = {
+    let mut names = [""; PixelFormat::all().len()];
+    let mut i = 0;
+    while i < names.len() {
+        names[i] = PixelFormat::all()[i].name();
+        i += 1;
+    }
+    names
+};
+
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl Serialize for PixelFormat {
+    /// Serializes the format as its lowercase [name](PixelFormat::name).
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<'de> Deserialize<'de> for PixelFormat {
+    /// Deserializes a format [name](PixelFormat::name), ignoring ASCII
+    /// case.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(PixelFormatVisitor)
+    }
+}
+
+/// Accepts a pixel format name.
+struct PixelFormatVisitor;
+
+impl<'de> Visitor<'de> for PixelFormatVisitor {
+    type Value = PixelFormat;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a pixel format name")
+    }
+
+    fn visit_str<E: de::Error>(self, name: &str) -> Result<PixelFormat, E> {
+        PixelFormat::from_name(name).ok_or_else(|| E::unknown_variant(name, &PIXEL_FORMAT_NAMES))
+    }
+}
+
+/// The serialized fields of [`HashOptions`], in order.
+const HASH_OPTIONS_FIELDS: [&str; 5] = [
+    "pixel_format",
+    "remove_border",
+    "no_rotate_flip",
+    "verbose",
+    "check_memory",
+];
+
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl Serialize for HashOptions {
+    /// Serializes the pixel format and the boolean flags.
+    ///
+    /// Alpha mode, Gray32 byte order, padding and EXIF orientation are not
+    /// serialized. Neither is
+    /// [`skip_buffer_validation`](HashOptions::skip_buffer_validation),
+    /// which is unsafe to enable and so is never read from data.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("HashOptions", HASH_OPTIONS_FIELDS.len())?;
+        state.serialize_field("pixel_format", &self.pixel_format)?;
+        state.serialize_field("remove_border", &self.remove_border)?;
+        state.serialize_field("no_rotate_flip", &self.no_rotate_flip)?;
+        state.serialize_field("verbose", &self.verbose)?;
+        state.serialize_field("check_memory", &self.check_memory)?;
+        state.end()
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<'de> Deserialize<'de> for HashOptions {
+    /// Deserializes the fields written by `Serialize`.
+    ///
+    /// Missing fields keep their [default](HashOptions::default); unknown
+    /// fields are an error, so a misspelled option is not silently
+    /// ignored.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("HashOptions", &HASH_OPTIONS_FIELDS, HashOptionsVisitor)
+    }
+}
+
+/// Builds [`HashOptions`] from a map of fields or a sequence in field order.
+struct HashOptionsVisitor;
+
+impl HashOptionsVisitor {
+    /// Reads the value of the field named `field` into `options`.
+    fn read_field<'de, A: MapAccess<'de>>(
+        field: &str,
+        map: &mut A,
+        options: &mut HashOptions,
+    ) -> Result<(), A::Error> {
+        match field {
+            "pixel_format" => options.pixel_format = map.next_value()?,
+            "remove_border" => options.remove_border = map.next_value()?,
+            "no_rotate_flip" => options.no_rotate_flip = map.next_value()?,
+            "verbose" => options.verbose = map.next_value()?,
+            "check_memory" => options.check_memory = map.next_value()?,
+            other => return Err(de::Error::unknown_field(other, &HASH_OPTIONS_FIELDS)),
+        }
+        Ok(())
+    }
+}
+
+impl<'de> Visitor<'de> for HashOptionsVisitor {
+    type Value = HashOptions;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("hash options")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<HashOptions, A::Error> {
+        let mut options = HashOptions::default();
+        let mut seen = [false; HASH_OPTIONS_FIELDS.len()];
+        while let Some(field) = map.next_key::<String>()? {
+            if let Some(i) = HASH_OPTIONS_FIELDS.iter().position(|&f| f == field) {
+                if seen[i] {
+                    return Err(de::Error::duplicate_field(HASH_OPTIONS_FIELDS[i]));
+                }
+                seen[i] = true;
+            }
+            Self::read_field(&field, &mut map, &mut options)?;
+        }
+        Ok(options)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<HashOptions, A::Error> {
+        let missing = |i| de::Error::invalid_length(i, &"5 hash option fields");
+        Ok(HashOptions {
+            pixel_format: seq.next_element()?.ok_or_else(|| missing(0))?,
+            remove_border: seq.next_element()?.ok_or_else(|| missing(1))?,
+            no_rotate_flip: seq.next_element()?.ok_or_else(|| missing(2))?,
+            verbose: seq.next_element()?.ok_or_else(|| missing(3))?,
+            check_memory: seq.next_element()?.ok_or_else(|| missing(4))?,
+            ..HashOptions::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_json_roundtrip() {
+        let mut store = HashStore::new();
+        store.insert("b", Hash::from_slice(&[0xAB, 0x01]).unwrap());
+        store.insert("a", Hash::new([7; HASH_SIZE]));
+
+        let json = serde_json::to_string(&store).unwrap();
+        assert!(json.starts_with(r#"{"b":"ab01","a":"0707"#));
+
+        let loaded: HashStore = serde_json::from_str(&json).unwrap();
+        let entries: Vec<_> = loaded.iter().collect();
+        let expected: Vec<_> = store.iter().collect();
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn test_store_json_rejects_bad_input() {
+        let duplicate = r#"{"a":"00","a":"01"}"#;
+        let err = serde_json::from_str::<HashStore>(duplicate).unwrap_err();
+        assert!(err.to_string().contains("duplicate id `a`"));
+
+        assert!(serde_json::from_str::<HashStore>(r#"{"a":"zz"}"#).is_err());
+    }
+
+    #[test]
+    fn test_hash_from_byte_sequence() {
+        let hash: Hash = serde_json::from_str("[1, 2, 3]").unwrap();
+        assert_eq!(hash.as_bytes(), &[1, 2, 3]);
+
+        let too_long = format!("{:?}", vec![0u8; HASH_SIZE + 1]);
+        assert!(serde_json::from_str::<Hash>(&too_long).is_err());
+    }
+
+    #[test]
+    fn test_pixel_format_json() {
+        for &format in PixelFormat::all() {
+            let json = serde_json::to_string(&format).unwrap();
+            assert_eq!(json, format!("\"{}\"", format.name()));
+            assert_eq!(serde_json::from_str::<PixelFormat>(&json).unwrap(), format);
+        }
+        assert_eq!(
+            serde_json::from_str::<PixelFormat>("\"BGRA\"").unwrap(),
+            PixelFormat::Bgra
+        );
+
+        let err = serde_json::from_str::<PixelFormat>("\"rgb565\"").unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("unknown variant `rgb565`, expected one of `rgb`, `bgr`"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_hash_options_json_roundtrip() {
+        let options = HashOptions::new()
+            .pixel_format(PixelFormat::Bgra)
+            .remove_border(true)
+            .check_memory(true);
+
+        let json = serde_json::to_string(&options).unwrap();
+        assert_eq!(
+            json,
+            r#"{"pixel_format":"bgra","remove_border":true,"no_rotate_flip":false,"verbose":false,"check_memory":true}"#
+        );
+        let loaded: HashOptions = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+
+        // Omitted fields keep their defaults.
+        let partial: HashOptions = serde_json::from_str(r#"{"no_rotate_flip":true}"#).unwrap();
+        assert_eq!(partial.pixel_format, PixelFormat::Rgb);
+        assert!(partial.no_rotate_flip && !partial.remove_border);
+
+        let err = serde_json::from_str::<HashOptions>(r#"{"remove_borders":true}"#).unwrap_err();
+        assert!(err.to_string().contains("unknown field `remove_borders`"));
+        let err = serde_json::from_str::<HashOptions>(r#"{"pixel_format":"rgb565"}"#).unwrap_err();
+        assert!(err.to_string().contains("unknown variant `rgb565`"));
+        assert!(
+            serde_json::from_str::<HashOptions>(r#"{"verbose":true,"verbose":false}"#).is_err()
+        );
+    }
+}
@@ -44,6 +44,10 @@
 //! - [`MockHashBuilder`]: Builder for creating custom test hashes
 //! - [`fixtures`]: Pre-built sample hashes for common test scenarios
 //! - [`generators`]: Proptest strategies for property-based testing
+//! - [`generate_corpus`] and [`generate_clustered_corpus`]: Reproducible
+//!   corpora for benchmarks
+//! - [`thread_local_generator`]: One reused SDK generator per test thread
+//! - [`calibration_image`]: A fixed image for checking a deployment's output
 //!
 //! ## Important Notes
 //!
@@ -51,9 +55,11 @@
 //! - Do not use these utilities to bypass PhotoDNA in production
 //! - These are for testing integration code, not the PhotoDNA algorithm
 
-use crate::{Hash, HASH_SIZE};
+use crate::{Generator, GeneratorOptions, Hash, Result, HASH_SIZE};
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
+use std::cell::RefCell;
+use std::rc::Rc;
 
 /// A builder for creating mock PhotoDNA hashes.
 ///
@@ -172,23 +178,190 @@ impl MockHashBuilder {
     /// * `base` - The base hash to create a variant of
     /// * `variance` - Amount of variance (0.0-1.0), where higher means more different
     pub fn variant(base: &Hash, variance: f64) -> Hash {
-        let mut rng = rand::thread_rng();
-        let mut bytes = [0u8; HASH_SIZE];
-        bytes.copy_from_slice(&base.as_bytes()[..base.len().min(HASH_SIZE)]);
+        perturb(base, variance, &mut rand::thread_rng())
+    }
+}
 
-        let change_probability = variance.clamp(0.0, 1.0);
-        let len = base.len().min(HASH_SIZE);
+/// Applies small random changes to a hash, as [`MockHashBuilder::variant`].
+fn perturb(base: &Hash, variance: f64, rng: &mut impl Rng) -> Hash {
+    let mut bytes = [0u8; HASH_SIZE];
+    let len = base.len().min(HASH_SIZE);
+    bytes[..len].copy_from_slice(&base.as_bytes()[..len]);
 
-        for b in bytes[..len].iter_mut() {
-            if rng.gen::<f64>() < change_probability {
-                // Apply small random change
-                let delta: i16 = rng.gen_range(-20..=20);
-                *b = (*b as i16).saturating_add(delta).clamp(0, 255) as u8;
-            }
+    let change_probability = variance.clamp(0.0, 1.0);
+
+    for b in bytes[..len].iter_mut() {
+        if rng.gen::<f64>() < change_probability {
+            // Apply small random change
+            let delta: i16 = rng.gen_range(-20..=20);
+            *b = (*b as i16).saturating_add(delta).clamp(0, 255) as u8;
         }
+    }
 
-        Hash::from_slice(&bytes[..len]).expect("valid hash length")
+    Hash::from_slice(&bytes[..len]).expect("valid hash length")
+}
+
+/// Generates a reproducible corpus of unrelated pseudo-random hashes.
+///
+/// The same `count` and `seed` always produce the same hashes, so
+/// benchmarks and integration tests can share data without the SDK.
+///
+/// # Examples
+///
+/// ```rust
+/// use photodna::test_utils::generate_corpus;
+///
+/// let corpus = generate_corpus(100, 7);
+/// assert_eq!(corpus.len(), 100);
+/// assert_eq!(corpus, generate_corpus(100, 7));
+/// ```
+pub fn generate_corpus(count: usize, seed: u64) -> Vec<Hash> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| {
+            let mut bytes = [0u8; HASH_SIZE];
+            rng.fill(&mut bytes[..]);
+            Hash::new(bytes)
+        })
+        .collect()
+}
+
+/// Generates a reproducible corpus of labeled near-duplicate groups.
+///
+/// Each of the `clusters` groups has a hidden random center, and each of
+/// its `per_cluster` members is a variant of that center, changed as by
+/// [`MockHashBuilder::variant`] with the given `variance`. Every hash is
+/// returned with the index of its cluster, grouped by cluster.
+///
+/// # Examples
+///
+/// ```rust
+/// use photodna::test_utils::generate_clustered_corpus;
+///
+/// let corpus = generate_clustered_corpus(3, 4, 0.05, 7);
+/// assert_eq!(corpus.len(), 12);
+/// assert_eq!(corpus[4].1, 1);
+/// ```
+pub fn generate_clustered_corpus(
+    clusters: usize,
+    per_cluster: usize,
+    variance: f64,
+    seed: u64,
+) -> Vec<(Hash, usize)> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut corpus = Vec::with_capacity(clusters * per_cluster);
+    for label in 0..clusters {
+        let mut center = [0u8; HASH_SIZE];
+        rng.fill(&mut center[..]);
+        let center = Hash::new(center);
+        for _ in 0..per_cluster {
+            corpus.push((perturb(&center, variance, &mut rng), label));
+        }
     }
+    corpus
+}
+
+thread_local! {
+    static GENERATOR: RefCell<Option<(GeneratorOptions, Rc<Generator>)>> = const { RefCell::new(None) };
+}
+
+/// Returns this thread's shared generator, creating it on first use.
+///
+/// Tests that need the real SDK can call this instead of
+/// [`Generator::new`] to load the library once per test thread rather than
+/// once per test. Calls on the same thread with equal `options` return the
+/// same generator; calling with different options replaces it, so
+/// alternating between two configurations reloads the library each time.
+///
+/// The generator stays loaded until the thread exits, or until it is
+/// replaced and every returned handle has been dropped. The test harness
+/// runs each test on its own thread by default, so sharing only pays off
+/// with `--test-threads=1` or when one test creates many generators. A
+/// failed initialization is not cached: the next call tries again.
+///
+/// # Errors
+///
+/// Returns any error from [`Generator::new`].
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use photodna::test_utils::thread_local_generator;
+/// use photodna::GeneratorOptions;
+///
+/// let generator = thread_local_generator(GeneratorOptions::default())?;
+/// println!("{}", generator.library_version_text().unwrap_or("unknown"));
+/// # Ok::<(), photodna::PhotoDnaError>(())
+/// ```
+pub fn thread_local_generator(options: GeneratorOptions) -> Result<Rc<Generator>> {
+    thread_local_with(options, Generator::new)
+}
+
+/// [`thread_local_generator`] with the constructor supplied, for testing.
+fn thread_local_with(
+    options: GeneratorOptions,
+    init: impl FnOnce(GeneratorOptions) -> Result<Generator>,
+) -> Result<Rc<Generator>> {
+    GENERATOR.with(|cell| {
+        let mut cached = cell.borrow_mut();
+        if let Some((cached_options, generator)) = cached.as_ref() {
+            if *cached_options == options {
+                return Ok(Rc::clone(generator));
+            }
+        }
+        let generator = Rc::new(init(options.clone())?);
+        *cached = Some((options, Rc::clone(&generator)));
+        Ok(generator)
+    })
+}
+
+/// The library version [`CALIBRATION_EXPECTED_HASH`] was recorded with.
+///
+/// Hashes of the same image can differ between SDK releases, so compare
+/// against the expected hash only when
+/// [`Generator::library_version_text`] reports this version. Set together
+/// with the expected hash, as a literal: it records the SDK the hash came
+/// from, not the one this crate is built against.
+pub const CALIBRATION_LIBRARY_VERSION: Option<&str> = crate::REFERENCE_LIBRARY_VERSION;
+
+/// The hash [`calibration_image`] should produce with library version
+/// [`CALIBRATION_LIBRARY_VERSION`], once recorded.
+///
+/// [`Generator::verify`] compares against the same value. Golden values
+/// can only be produced by the licensed SDK, so this is `None` until a
+/// maintainer records one from a reference run. Until then, record the hash
+/// from a known-good installation and compare against that.
+pub const CALIBRATION_EXPECTED_HASH: Option<[u8; HASH_SIZE]> = crate::REFERENCE_EXPECTED_HASH;
+
+/// Returns the calibration image as tightly packed RGB pixels with its
+/// width and height.
+///
+/// A 64×64 two-axis gradient with a checkerboard overlay, the same image
+/// [`Generator::verify`] hashes. Hashing it and comparing against
+/// [`CALIBRATION_EXPECTED_HASH`] checks that a deployment's SDK produces
+/// the expected output, not just some output.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use photodna::test_utils::{
+///     calibration_image, CALIBRATION_EXPECTED_HASH, CALIBRATION_LIBRARY_VERSION,
+/// };
+/// use photodna::{Generator, GeneratorOptions};
+///
+/// let generator = Generator::new(GeneratorOptions::default())?;
+/// let (image, width, height) = calibration_image();
+/// let hash = generator.compute_hash_rgb(&image, width, height)?;
+/// if let (Some(expected), Some(version)) = (CALIBRATION_EXPECTED_HASH, CALIBRATION_LIBRARY_VERSION) {
+///     if generator.library_version_text() == Some(version) {
+///         assert_eq!(hash.as_bytes(), &expected[..]);
+///     }
+/// }
+/// # Ok::<(), photodna::PhotoDnaError>(())
+/// ```
+pub fn calibration_image() -> (Vec<u8>, u32, u32) {
+    let (image, side) = crate::reference_image();
+    (image, side, side)
 }
 
 /// Pre-built sample hashes for common test scenarios.
@@ -335,6 +508,37 @@ pub mod generators {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::mock::MockBackend;
+    use crate::{HashOptions, PhotoDnaError};
+
+    #[test]
+    fn test_calibration_image() {
+        let (image, width, height) = calibration_image();
+        assert_eq!((width, height), (64, 64));
+        assert_eq!(image.len(), 64 * 64 * 3);
+        assert_eq!(calibration_image().0, image);
+
+        let generator = Generator::from_backend(MockBackend {
+            echo_input: true,
+            ..Default::default()
+        });
+        let hash = generator.compute_hash_rgb(&image, width, height).unwrap();
+        assert_eq!(
+            hash,
+            generator
+                .compute_hash(&image, width, height, HashOptions::new())
+                .unwrap()
+        );
+        assert_eq!(hash.as_bytes(), &image[..HASH_SIZE]);
+    }
+
+    #[test]
+    fn test_calibration_constants_are_set_together() {
+        assert_eq!(
+            CALIBRATION_EXPECTED_HASH.is_some(),
+            CALIBRATION_LIBRARY_VERSION.is_some()
+        );
+    }
 
     #[test]
     fn test_builder_with_seed_is_deterministic() {
@@ -397,6 +601,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_generated_corpora_are_reproducible() {
+        assert_eq!(generate_corpus(20, 1), generate_corpus(20, 1));
+        assert_ne!(generate_corpus(20, 1), generate_corpus(20, 2));
+
+        let clustered = generate_clustered_corpus(4, 5, 0.1, 9);
+        assert_eq!(clustered, generate_clustered_corpus(4, 5, 0.1, 9));
+        assert_eq!(clustered.len(), 20);
+
+        // Members of a cluster are far closer to each other than to others.
+        let (a, b, other) = (&clustered[0], &clustered[1], &clustered[5]);
+        assert_eq!((a.1, b.1, other.1), (0, 0, 1));
+        assert!(a.0.distance(&b.0) * 5.0 < a.0.distance(&other.0));
+    }
+
     #[test]
     fn test_fixtures_are_consistent() {
         let a1 = fixtures::sample_hash_a();
@@ -440,4 +659,28 @@ mod tests {
             last_diff = diff;
         }
     }
+
+    #[test]
+    fn test_thread_local_generator_is_reused() {
+        let mock = |_| Ok(Generator::from_backend(MockBackend::default()));
+        let options = GeneratorOptions::default();
+
+        let first = thread_local_with(options.clone(), mock).unwrap();
+        let again = thread_local_with(options.clone(), |_| unreachable!()).unwrap();
+        assert!(Rc::ptr_eq(&first, &again));
+
+        let failed = thread_local_with(options.clone().max_threads(2), |_| {
+            Err(PhotoDnaError::LibraryFailure)
+        });
+        assert_eq!(failed.err(), Some(PhotoDnaError::LibraryFailure));
+        let other = thread_local_with(options.clone().max_threads(2), mock).unwrap();
+        assert!(!Rc::ptr_eq(&first, &other));
+
+        let elsewhere = std::thread::spawn(move || {
+            let generator = thread_local_with(options, mock).unwrap();
+            Rc::strong_count(&generator)
+        });
+        // Only the returned handle and this thread's cache hold it.
+        assert_eq!(elsewhere.join().unwrap(), 2);
+    }
 }
@@ -0,0 +1,180 @@
+//! Capturing the library's verbose output (requires the `capture-verbose`
+//! feature; Unix only).
+//!
+//! The library writes its diagnostics straight to file descriptor 2, so the
+//! only way to collect them is to point that descriptor somewhere else for
+//! the duration of the call.
+
+use crate::{Generator, Hash, HashOptions, PhotoDnaError, Result};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::io::AsRawFd;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+/// Serializes captures: descriptor 2 is shared by the whole process.
+static CAPTURE: Mutex<()> = Mutex::new(());
+
+impl Generator {
+    /// Computes a PhotoDNA hash with verbose output enabled, returning the
+    /// library's diagnostics along with the result.
+    ///
+    /// Behaves like [`compute_hash`](Self::compute_hash) with
+    /// [`HashOptions::verbose`] set, except that everything the library
+    /// writes to standard error during the call is collected and returned
+    /// instead of printed. The output is returned even when hashing fails,
+    /// which is when it is most useful.
+    ///
+    /// # Platform limitations
+    ///
+    /// The output is captured by redirecting file descriptor 2 to a
+    /// temporary file for the duration of the call. Standard error is
+    /// process-wide, so:
+    ///
+    /// - anything other threads write to standard error during the call is
+    ///   captured too, and does not appear on the terminal;
+    /// - concurrent calls to this method are serialized, although other
+    ///   hashing calls are not blocked;
+    /// - output the library buffers and writes after the call returns is
+    ///   not captured.
+    ///
+    /// Only available on Unix. Non-UTF-8 output is converted lossily.
+    ///
+    /// # Errors
+    ///
+    /// The result holds any error from
+    /// [`compute_hash`](Self::compute_hash), or
+    /// [`PhotoDnaError::VerboseCapture`] if standard error could not be
+    /// redirected, in which case the image is not hashed and the output is
+    /// empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let (result, log) = generator.compute_hash_verbose(&pixels, 640, 480, HashOptions::new());
+    /// if let Err(e) = result {
+    ///     eprintln!("hashing failed: {e}\n{log}");
+    /// }
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(all(unix, feature = "capture-verbose"))))]
+    pub fn compute_hash_verbose(
+        &self,
+        image_data: &[u8],
+        width: u32,
+        height: u32,
+        options: HashOptions,
+    ) -> (Result<Hash>, String) {
+        let _lock = CAPTURE.lock().unwrap_or_else(PoisonError::into_inner);
+        let capture = match StderrCapture::start() {
+            Ok(capture) => capture,
+            Err(e) => {
+                return (
+                    Err(PhotoDnaError::VerboseCapture(e.to_string())),
+                    String::new(),
+                )
+            }
+        };
+        let result = self.compute_hash(image_data, width, height, options.verbose(true));
+        match capture.finish() {
+            Ok(output) => (result, output),
+            Err(e) => (
+                result.and(Err(PhotoDnaError::VerboseCapture(e.to_string()))),
+                String::new(),
+            ),
+        }
+    }
+}
+
+/// Standard error redirected to a temporary file until finished or dropped.
+struct StderrCapture {
+    /// Receives everything written to descriptor 2.
+    file: File,
+    /// Duplicate of the original descriptor 2, restored afterwards.
+    saved: libc::c_int,
+}
+
+impl StderrCapture {
+    fn start() -> io::Result<Self> {
+        static NEXT: AtomicU32 = AtomicU32::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "photodna-verbose-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        // The open descriptor keeps the file alive.
+        std::fs::remove_file(&path)?;
+
+        io::stderr().flush()?;
+        // SAFETY: `dup` and `dup2` only operate on descriptor numbers; 2 is
+        // always a valid argument, and `file` is open.
+        unsafe {
+            let saved = libc::dup(libc::STDERR_FILENO);
+            if saved < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if libc::dup2(file.as_raw_fd(), libc::STDERR_FILENO) < 0 {
+                let error = io::Error::last_os_error();
+                libc::close(saved);
+                return Err(error);
+            }
+            Ok(Self { file, saved })
+        }
+    }
+
+    /// Restores standard error and returns what was written to it.
+    fn finish(mut self) -> io::Result<String> {
+        self.restore();
+        let mut output = Vec::new();
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.read_to_end(&mut output)?;
+        Ok(String::from_utf8_lossy(&output).into_owned())
+    }
+
+    fn restore(&mut self) {
+        if self.saved < 0 {
+            return;
+        }
+        // SAFETY: `saved` is a descriptor this capture owns, and flushing
+        // every C stream is always permitted.
+        unsafe {
+            libc::fflush(std::ptr::null_mut());
+            libc::dup2(self.saved, libc::STDERR_FILENO);
+            libc::close(self.saved);
+        }
+        self.saved = -1;
+    }
+}
+
+impl Drop for StderrCapture {
+    fn drop(&mut self) {
+        self.restore();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::mock::MockBackend;
+
+    #[test]
+    fn test_compute_hash_verbose_captures_stderr() {
+        let generator = Generator::from_backend(MockBackend::default());
+        let pixels = vec![0u8; 64 * 64 * 3];
+
+        let (result, output) = generator.compute_hash_verbose(&pixels, 64, 64, HashOptions::new());
+        assert!(result.is_ok());
+        assert!(output.contains("mock: edge_hash"), "{output:?}");
+
+        // Rejected before the library is called, so it prints nothing.
+        let (result, output) =
+            generator.compute_hash_verbose(&pixels, 100, 100, HashOptions::new());
+        assert!(matches!(result, Err(PhotoDnaError::BufferTooSmall { .. })));
+        assert!(!output.contains("mock: edge_hash"), "{output:?}");
+    }
+}